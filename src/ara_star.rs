@@ -0,0 +1,316 @@
+use crate::{
+    map::{Callbacks, MapCallbacks, RawCallbacks},
+    path_finder_lowest_in_open_set, path_finder_open_set_is_empty, path_finder_open_set_pop,
+    path_finder_open_set_push, path_finder_reconstruct_path, Heuristic, Map, PathFinderBase,
+    PathFinderError, Storage,
+};
+use std::{mem, os::raw::c_void};
+
+const INFINITY: i32 = i32::MAX;
+
+/// An anytime planner after ARA*. It first finds a path with the large `heuristic_weight` of
+/// the path finder, then lowers the weight by a step after every path found and repairs the
+/// search to improve on it, until the path is optimal. Each call to [`step`](Self::step) expands
+/// a single cell, so the search can be spread over as many calls as time allows.
+///
+/// `score_func` is ignored.
+#[derive(Debug, Clone)]
+pub struct AraStar<S: Storage> {
+    /// The map and the published answer of the anytime search: [`path`](PathFinderBase::path)
+    /// is the best path found so far, and `path_weight` the factor by which it may exceed the
+    /// optimal cost. `parents` and `g_score` are the working state of the current iteration,
+    /// with an infinite g_score for cells it has not reached.
+    pub path_finder: PathFinderBase<S>,
+    weight: f32,
+    weight_step: f32,
+    /* Closed cells whose g_score improved, to reopen with the next weight, and whether each cell
+     * is one of them */
+    inconsistent: Vec<usize>,
+    is_inconsistent: Vec<bool>,
+    path: Vec<[i32; 2]>,
+    cost: Option<i32>,
+    paths_found: usize,
+    done: bool,
+}
+
+impl<S: Storage> AraStar<S> {
+    /// Starts planning from the start to the end of `path_finder`, with its `heuristic_weight`
+    /// for the first path and `weight_step` less for every next one.
+    pub fn new(
+        mut path_finder: PathFinderBase<S>,
+        weight_step: f32,
+    ) -> Result<Self, PathFinderError> {
+        path_finder.check_endpoints()?;
+
+        let start = path_finder.start as usize;
+        for index in 0..path_finder.size() {
+            path_finder.state[index] &= !0xe;
+            path_finder.g_score[index] = INFINITY;
+        }
+        path_finder.open_set_size = 0;
+        path_finder.has_path = 0;
+        path_finder.parents[start] = start as i32;
+        path_finder.g_score[start] = 0;
+        path_finder.f_score[start] = 0;
+        path_finder.begin();
+
+        Ok(AraStar {
+            weight: path_finder.heuristic_weight.max(1.0),
+            is_inconsistent: vec![false; path_finder.size()],
+            path_finder,
+            weight_step,
+            inconsistent: Vec::new(),
+            path: Vec::new(),
+            cost: None,
+            paths_found: 0,
+            done: false,
+        })
+    }
+
+    /// The weight of the running search.
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// The best path so far, from start to end. Empty until the first path is found.
+    pub fn path(&self) -> &[[i32; 2]] {
+        &self.path
+    }
+
+    /// The cost of the best path so far.
+    pub fn cost(&self) -> Option<i32> {
+        self.cost
+    }
+
+    /// The number of paths found so far, one per weight. A path may be no better than the
+    /// previous one.
+    pub fn paths_found(&self) -> usize {
+        self.paths_found
+    }
+
+    /// Expands a cell, or publishes the path once the search with the current weight is over.
+    /// Returns whether the path may still improve.
+    pub fn step(&mut self, data: *mut c_void) -> bool {
+        self.step_by(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
+
+    /// Like [`step`](Self::step), taking the traversal costs from `map`. Passability still comes
+    /// from the state of [`path_finder`](Self::path_finder).
+    pub fn step_in<M: Map + ?Sized>(&mut self, map: &mut M) -> bool {
+        self.step_by(&mut MapCallbacks(map))
+    }
+
+    fn step_by<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> bool {
+        if self.done {
+            return false;
+        }
+
+        /* The search with the current weight is over once end would be expanded next */
+        let end = self.path_finder.end as usize;
+        if path_finder_open_set_is_empty(&self.path_finder) == 0 {
+            let lowest = path_finder_lowest_in_open_set(&self.path_finder) as usize;
+            if self.path_finder.g_score[end] > self.path_finder.f_score[lowest] {
+                self.expand_lowest(callbacks);
+                return true;
+            }
+        }
+
+        if self.path_finder.g_score[end] == INFINITY {
+            self.done = true;
+            return false;
+        }
+
+        self.publish(callbacks);
+        if self.weight <= 1.0 || self.path_finder.path_weight <= 1.0 {
+            self.done = true;
+            return false;
+        }
+
+        let weight = self.weight - self.weight_step;
+        self.weight = if weight < self.weight {
+            weight.max(1.0)
+        } else {
+            1.0
+        };
+        self.reopen(callbacks);
+        true
+    }
+
+    fn expand_lowest<C: Callbacks<S>>(&mut self, callbacks: &mut C) {
+        let current = path_finder_open_set_pop(&mut self.path_finder) as usize;
+        self.path_finder.state[current] |= 0x4;
+
+        let [col, row] = self.path_finder.position(current);
+        let current_g_score = self.path_finder.g_score[current];
+        for &offset in self.path_finder.neighbor_offsets() {
+            let (n, step_cost) = match self.path_finder.neighbor(col, row, offset) {
+                Some(neighbor) => neighbor,
+                None => continue,
+            };
+            if self.path_finder.state[n] & 0x1 == 0 {
+                continue;
+            }
+
+            let [neighbor_col, neighbor_row] = self.path_finder.position(n);
+            let g_score = current_g_score
+                + step_cost
+                    * callbacks.step_cost(&mut self.path_finder, neighbor_col, neighbor_row);
+            if g_score < self.path_finder.g_score[n] {
+                self.path_finder.parents[n] = current as i32;
+                self.path_finder.g_score[n] = g_score;
+                if self.path_finder.state[n] & 0x4 == 0 {
+                    self.push(callbacks, n);
+                } else if !self.is_inconsistent[n] {
+                    self.is_inconsistent[n] = true;
+                    self.inconsistent.push(n);
+                }
+            }
+        }
+    }
+
+    fn push<C: Callbacks<S>>(&mut self, callbacks: &mut C, index: usize) {
+        let heuristic = callbacks.heuristic(&mut self.path_finder, index as i32);
+        let heuristic = (f64::from(self.weight) * f64::from(heuristic)).round() as i32;
+        self.path_finder.f_score[index] = self.path_finder.g_score[index] + heuristic;
+        path_finder_open_set_push(&mut self.path_finder, index as i32);
+    }
+
+    /* Marks the path found with the current weight and bounds its cost by the lowest unweighted
+     * f_score still open or inconsistent */
+    fn publish<C: Callbacks<S>>(&mut self, callbacks: &mut C) {
+        let path_finder = &mut self.path_finder;
+        for index in 0..path_finder.size() {
+            path_finder.state[index] &= !0x8;
+        }
+        path_finder_reconstruct_path(path_finder);
+        path_finder.has_path = 1;
+        self.path = path_finder.path();
+        self.cost = Some(path_finder.g_score[path_finder.end as usize]);
+        self.paths_found += 1;
+
+        let open: Vec<_> = path_finder.open_set.as_ref()[..path_finder.open_set_size as usize]
+            .iter()
+            .map(|&index| index as usize)
+            .collect();
+        let lowest = open
+            .into_iter()
+            .chain(self.inconsistent.iter().copied())
+            .map(|index| {
+                let heuristic = callbacks.heuristic(path_finder, index as i32);
+                f64::from(path_finder.g_score[index]) + f64::from(heuristic)
+            })
+            .fold(f64::INFINITY, f64::min);
+        let cost = f64::from(self.cost.unwrap());
+        path_finder.path_weight =
+            (cost / lowest.max(1.0)).clamp(1.0, f64::from(self.weight)) as f32;
+    }
+
+    /* Opens the inconsistent cells again and reorders the open set for the new weight */
+    fn reopen<C: Callbacks<S>>(&mut self, callbacks: &mut C) {
+        let path_finder = &mut self.path_finder;
+        let size = path_finder.open_set_size as usize;
+        let mut open: Vec<usize> = path_finder.open_set.as_ref()[..size]
+            .iter()
+            .map(|&index| index as usize)
+            .collect();
+        for &index in &self.inconsistent {
+            self.is_inconsistent[index] = false;
+        }
+        open.append(&mut mem::take(&mut self.inconsistent));
+        for index in 0..path_finder.size() {
+            path_finder.state[index] &= !0x6;
+        }
+        path_finder.open_set_size = 0;
+
+        for index in open {
+            self.push(callbacks, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{cross_check, path_cost, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    #[test]
+    fn ara_star() {
+        let mut rng = SmallRng::seed_from_u64(20);
+        let (mut first_steps, mut optimal_closed) = (0, 0);
+        for _map in 0..20 {
+            let mut path_finder = random_grid(
+                &mut rng,
+                [60, 40],
+                Movement::EightConnected,
+                [1, 4],
+                &[[0, 0], [59, 39]],
+            );
+            path_finder.set_end(59, 39).unwrap();
+            path_finder.heuristic_weight = 3.0;
+            let costs: Vec<i32> = (0..60 * 40).map(|_| rng.gen_range(1, 4)).collect();
+            let passable: Vec<bool> = path_finder
+                .state
+                .iter()
+                .map(|&state| state & 0x1 != 0)
+                .collect();
+            let mut map = FnMap::new(
+                |col, row| passable[(row * 60 + col) as usize],
+                |col, row| costs[(row * 60 + col) as usize],
+            );
+
+            let mut planner = AraStar::new(path_finder, 0.5).unwrap();
+            let (mut steps, mut paths) = (0, Vec::new());
+            while planner.step_in(&mut map) {
+                steps += 1;
+                if planner.paths_found() > paths.len() {
+                    paths.push((planner.cost().unwrap(), planner.path_finder.path_weight));
+                    if paths.len() == 1 {
+                        first_steps += steps;
+                    }
+                }
+            }
+            assert!(!planner.step_in(&mut map));
+            let optimal = match cross_check(&planner.path_finder, planner.cost().is_some(), &costs)
+            {
+                Some(optimal) => optimal,
+                None => {
+                    assert!(planner.path().is_empty());
+                    continue;
+                }
+            };
+            optimal_closed += optimal
+                .state
+                .iter()
+                .filter(|&&state| state & 0x5 == 0x5)
+                .count();
+            let optimal = optimal.g_score[2399];
+
+            /* Every path is at least as cheap as the last under a bound at least as tight, the
+             * last one published by the call returning false */
+            paths.extend(
+                planner
+                    .cost()
+                    .map(|cost| (cost, planner.path_finder.path_weight)),
+            );
+            paths.dedup();
+            assert!(paths.len() > 1);
+            assert_eq!(paths.last(), Some(&(optimal, 1.0)));
+            for pair in paths.windows(2) {
+                assert!(pair[1].0 <= pair[0].0 && pair[1].1 <= pair[0].1);
+            }
+            for &(cost, bound) in &paths {
+                assert!(bound <= 3.0 && cost as f32 <= optimal as f32 * bound);
+            }
+
+            assert_eq!(planner.path(), planner.path_finder.path());
+            assert_eq!(path_cost(&planner.path_finder, &costs), optimal);
+        }
+        assert!(first_steps * 2 < optimal_closed);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{HeapPathFinder, Map, PathFinderBase, PathFinderError, Storage};
+use std::{convert::TryFrom, iter, str::FromStr};
+
+/*
+ * Grids drawn as text, one character per cell and one line per row, bare or inside a border as
+ * the diagrams of the tests are:
+ *
+ *   S..#....             01234567
+ *   .f.#..1.            /--------\
+ *   ...#...E           0|S  #    |
+ *                      1| f #  1 |
+ *                      2|   #   E|
+ *                       \--------/
+ *
+ * `#` is a wall, `S` and `E` the start and the end, a digit from `0` to `9` or `a` to `f` the
+ * danger of a passable cell, from 0 to 15, which biases its f_score as `score_func` does. Any
+ * other character is a passable cell without danger, which includes the marks written by
+ * `to_ascii` so that its output parses back. Short lines are padded with passable cells. A
+ * border keeps only the cells between the first and the last `|` of every line inside it, so
+ * that row and column labels around it are ignored.
+ */
+
+/// A grid parsed from ASCII art, see [`FromStr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsciiMap {
+    pub cols: i32,
+    pub rows: i32,
+    /// The column and row of the `S` cell, if any.
+    pub start: Option<[i32; 2]>,
+    /// The column and row of the `E` cell, if any.
+    pub end: Option<[i32; 2]>,
+    /// The passability of every cell, row by row.
+    pub passable: Vec<bool>,
+    /// The danger of every cell, row by row. 0 where the text has no digit.
+    pub dangers: Vec<i32>,
+}
+
+impl FromStr for AsciiMap {
+    type Err = PathFinderError;
+
+    /// Parses the art, ignoring the empty lines around it or whatever lies outside its border.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<_> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect();
+        let lines = match lines.iter().position(|line| is_border(line, '/', '\\')) {
+            Some(top) => {
+                let inside = &lines[top + 1..];
+                let bottom = inside
+                    .iter()
+                    .position(|line| is_border(line, '\\', '/'))
+                    .ok_or(PathFinderError::InvalidFormat)?;
+                inside[..bottom]
+                    .iter()
+                    .map(|line| match (line.find('|'), line.rfind('|')) {
+                        (Some(first), Some(last)) if first < last => Ok(&line[first + 1..last]),
+                        _ => Err(PathFinderError::InvalidFormat),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            None => {
+                let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(0);
+                let last = lines
+                    .iter()
+                    .rposition(|line| !line.is_empty())
+                    .map_or(0, |last| last + 1);
+                lines[first..last.max(first)].to_vec()
+            }
+        };
+
+        let cols = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let rows = lines.len();
+        let (cols, rows) = match (i32::try_from(cols), i32::try_from(rows)) {
+            (Ok(cols), Ok(rows)) if cols > 0 && rows > 0 => (cols, rows),
+            _ => return Err(PathFinderError::InvalidSize),
+        };
+
+        let mut map = AsciiMap {
+            cols,
+            rows,
+            start: None,
+            end: None,
+            passable: Vec::with_capacity(cols as usize * rows as usize),
+            dangers: Vec::with_capacity(cols as usize * rows as usize),
+        };
+        for (row, line) in (0..).zip(lines) {
+            let cells = line.chars().chain(iter::repeat('.'));
+            for (col, c) in (0..cols).zip(cells) {
+                let endpoint = match c {
+                    'S' => Some(&mut map.start),
+                    'E' => Some(&mut map.end),
+                    _ => None,
+                };
+                if let Some(endpoint) = endpoint {
+                    if endpoint.replace([col, row]).is_some() {
+                        return Err(PathFinderError::InvalidFormat);
+                    }
+                }
+
+                map.passable.push(c != '#');
+                let digit = c.to_digit(16).filter(|_| !c.is_ascii_uppercase());
+                map.dangers.push(digit.map_or(0, |danger| danger as i32));
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/* Whether the line is the top or bottom of a border, such as `/---\` */
+fn is_border(line: &str, first: char, last: char) -> bool {
+    let line = line.trim();
+    line.len() > 2
+        && line.starts_with(first)
+        && line.ends_with(last)
+        && line[1..line.len() - 1].chars().all(|c| c == '-')
+}
+
+impl Map for AsciiMap {
+    fn is_passable(&mut self, col: i32, row: i32) -> bool {
+        self.passable[(row * self.cols + col) as usize]
+    }
+
+    fn score(&mut self, col: i32, row: i32) -> i32 {
+        self.dangers[(row * self.cols + col) as usize]
+    }
+}
+
+impl AsciiMap {
+    /// A path finder the size of the map, filled from it and with its start and end. Dangers
+    /// only apply when searching with the map, as in [`find_in`](PathFinderBase::find_in).
+    pub fn path_finder(&self) -> Result<HeapPathFinder, PathFinderError> {
+        let mut path_finder = HeapPathFinder::new(self.cols, self.rows)?;
+        path_finder.fill_with(|col, row| self.passable[(row * self.cols + col) as usize])?;
+        if let Some([col, row]) = self.start {
+            path_finder.set_start(col, row)?;
+        }
+        if let Some([col, row]) = self.end {
+            path_finder.set_end(col, row)?;
+        }
+        Ok(path_finder)
+    }
+}
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Draws the grid as ASCII art, one line per row: `S` and `E` for start and end, `#` for
+    /// walls, `*` for the path, `x` for closed and `o` for open cells, and `.` for the others.
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity(self.size() + self.rows.max(0) as usize);
+        for index in 0..self.size() {
+            let state = self.state[index];
+            text.push(if index as i32 == self.start {
+                'S'
+            } else if index as i32 == self.end {
+                'E'
+            } else if state & 0x1 == 0 {
+                '#'
+            } else if state & 0x8 != 0 {
+                '*'
+            } else if state & 0x4 != 0 {
+                'x'
+            } else if state & 0x2 != 0 {
+                'o'
+            } else {
+                '.'
+            });
+
+            if (index as i32 + 1) % self.cols == 0 {
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn ascii() {
+        let text = "
+S..#....
+.ff#..6.
+.f.#...E
+.....
+";
+        let mut map: AsciiMap = text.parse().unwrap();
+        assert_eq!([map.cols, map.rows], [8, 4]);
+        assert_eq!(map.start, Some([0, 0]));
+        assert_eq!(map.end, Some([7, 2]));
+        assert_eq!(map.dangers[9..16], [15, 15, 0, 0, 0, 6, 0]);
+        assert!(map.passable[28..].iter().all(|&passable| passable));
+
+        let mut path_finder = map.path_finder().unwrap();
+        assert_eq!(path_finder.find_in(&mut map), Ok(true));
+        assert_eq!(path_finder.g_score[23], 11);
+        let drawn = path_finder.to_ascii();
+        assert_eq!(
+            drawn,
+            "\
+Sxx#....
+*oo#ooo.
+*ox#***E
+*****oo.
+"
+        );
+
+        let parsed: AsciiMap = drawn.parse().unwrap();
+        assert_eq!(parsed.passable, map.passable);
+        assert_eq!([parsed.start, parsed.end], [map.start, map.end]);
+        assert!(parsed.dangers.iter().all(|&danger| danger == 0));
+
+        /* Labels around a border are left out, blanks inside it are passable */
+        let bordered: AsciiMap = "
+   01234567
+  /--------\\
+ 0|S  #    |
+ 1| ff#  6 |
+ 2| f #   E|
+ 3|        |
+  \\--------/
+"
+        .parse()
+        .unwrap();
+        assert_eq!(bordered, map);
+
+        assert_eq!(
+            "S.S".parse::<AsciiMap>(),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(
+            "/--\\\n|S.|\n".parse::<AsciiMap>(),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(
+            "/--\\\n|S.\n\\--/\n".parse::<AsciiMap>(),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(
+            "\n\n".parse::<AsciiMap>(),
+            Err(PathFinderError::InvalidSize)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    map::{Callbacks, MapCallbacks, RawCallbacks},
+    path_finder_reconstruct_path, Heuristic, Map, PathFinderBase, PathFinderError, Storage,
+};
+use std::{cmp::Reverse, collections::BinaryHeap, os::raw::c_void};
+
+/* One half of a bidirectional search, growing from `source` towards `goal` */
+struct Frontier {
+    goal: [i32; 2],
+    g_score: Vec<i32>,
+    parents: Vec<i32>,
+    closed: Vec<bool>,
+    /* Cells may be pushed again when their score improves, the stale entries are closed */
+    open: BinaryHeap<Reverse<(i32, usize)>>,
+}
+
+impl Frontier {
+    fn new(size: usize, source: usize, goal: [i32; 2]) -> Self {
+        let mut frontier = Frontier {
+            goal,
+            g_score: vec![i32::MAX; size],
+            parents: vec![-1; size],
+            closed: vec![false; size],
+            open: BinaryHeap::new(),
+        };
+        frontier.g_score[source] = 0;
+        frontier.open.push(Reverse((0, source)));
+        frontier
+    }
+
+    fn min_f_score(&mut self) -> Option<i32> {
+        while let Some(&Reverse((f_score, index))) = self.open.peek() {
+            if !self.closed[index] {
+                return Some(f_score);
+            }
+            self.open.pop();
+        }
+
+        None
+    }
+}
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Searches from start and end at the same time until the two frontiers meet on a shortest
+    /// path. Leaves the path in `parents` and `g_score` like [`find`](Self::find), and closes the
+    /// cells expanded from either side. Only the built-in `heuristic` is used, and `score_func`
+    /// is ignored since a biased score would break the stopping rule.
+    pub fn find_bidirectional(&mut self, data: *mut c_void) -> Result<bool, PathFinderError> {
+        self.find_bidirectional_by(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
+
+    /// Fills the grid from `map` and searches it from both start and end.
+    pub fn find_bidirectional_in<M: Map + ?Sized>(
+        &mut self,
+        map: &mut M,
+    ) -> Result<bool, PathFinderError> {
+        self.fill_from(map)?;
+        self.find_bidirectional_by(&mut MapCallbacks(map))
+    }
+
+    fn find_bidirectional_by<C: Callbacks<S>>(
+        &mut self,
+        callbacks: &mut C,
+    ) -> Result<bool, PathFinderError> {
+        self.check_endpoints()?;
+
+        let (start, end) = (self.start as usize, self.end as usize);
+        let mut forward = Frontier::new(self.size(), start, self.position(end));
+        let mut backward = Frontier::new(self.size(), end, self.position(start));
+
+        /* The cost of the best path through a cell reached from both sides so far */
+        let mut best = (start == end).then_some((0, start));
+        while let (Some(forward_f_score), Some(backward_f_score)) =
+            (forward.min_f_score(), backward.min_f_score())
+        {
+            if best.is_some_and(|(cost, _)| forward_f_score.max(backward_f_score) >= cost) {
+                break;
+            }
+
+            let meeting = if forward.open.len() <= backward.open.len() {
+                self.expand_frontier(callbacks, &mut forward, &backward, true)
+            } else {
+                self.expand_frontier(callbacks, &mut backward, &forward, false)
+            };
+            if let Some(meeting) = meeting {
+                if best.map_or(true, |(cost, _)| meeting.0 < cost) {
+                    best = Some(meeting);
+                }
+            }
+        }
+
+        for index in 0..self.size() {
+            if forward.closed[index] || backward.closed[index] {
+                self.state[index] |= 0x4;
+            }
+        }
+
+        let (cost, meeting) = match best {
+            Some(best) => best,
+            None => {
+                self.has_path = 0;
+                return Ok(false);
+            }
+        };
+
+        /* Forward parents lead back from the meeting cell to start, backward ones on to end */
+        let mut index = meeting;
+        while index != start {
+            self.parents[index] = forward.parents[index];
+            self.g_score[index] = forward.g_score[index];
+            index = forward.parents[index] as usize;
+        }
+        let mut index = meeting;
+        while index != end {
+            let next = backward.parents[index] as usize;
+            self.parents[next] = index as i32;
+            self.g_score[next] = cost - backward.g_score[next];
+            index = next;
+        }
+
+        path_finder_reconstruct_path(self);
+        self.has_path = 1;
+        self.path_weight = 1.0;
+        Ok(true)
+    }
+
+    /* Closes the lowest cell of `frontier` and returns the cheapest path found through one of
+     * its neighbours already reached by `other`, with that neighbour */
+    fn expand_frontier<C: Callbacks<S>>(
+        &mut self,
+        callbacks: &mut C,
+        frontier: &mut Frontier,
+        other: &Frontier,
+        forward: bool,
+    ) -> Option<(i32, usize)> {
+        let Reverse((_, current)) = frontier.open.pop()?;
+        frontier.closed[current] = true;
+
+        let [col, row] = self.position(current);
+        let heuristic = self.builtin_heuristic();
+        let mut meeting: Option<(i32, usize)> = None;
+        for &offset in self.neighbor_offsets() {
+            let (n, step_cost) = match self.neighbor(col, row, offset) {
+                Some(neighbor) => neighbor,
+                None => continue,
+            };
+            if self.state[n] & 0x1 == 0 || frontier.closed[n] {
+                continue;
+            }
+
+            /* Costs are paid on entering a cell, which is the current one when going backwards */
+            let [neighbor_col, neighbor_row] = self.position(n);
+            let cost = if forward {
+                callbacks.step_cost(self, neighbor_col, neighbor_row)
+            } else {
+                callbacks.step_cost(self, col, row)
+            };
+            let g_score = frontier.g_score[current] + step_cost * cost;
+            if g_score >= frontier.g_score[n] {
+                continue;
+            }
+
+            frontier.g_score[n] = g_score;
+            frontier.parents[n] = current as i32;
+            let h_score = heuristic.estimate(
+                [neighbor_col, neighbor_row],
+                frontier.goal,
+                self.step_costs(),
+            );
+            frontier.open.push(Reverse((g_score + h_score, n)));
+
+            if other.g_score[n] != i32::MAX {
+                let cost = g_score + other.g_score[n];
+                if meeting.map_or(true, |(best, _)| cost < best) {
+                    meeting = Some((cost, n));
+                }
+            }
+        }
+
+        meeting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{create_complex_map, cross_check, path_cost, random_endpoints, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn find_bidirectional() {
+        let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder_fill(&mut path_finder);
+
+        let mut one_way = path_finder;
+        one_way.find(null_mut()).unwrap();
+        let mut both_ways = path_finder;
+        assert_eq!(
+            path_finder_find_bidirectional(&mut both_ways, null_mut()),
+            PATH_FINDER_OK
+        );
+        assert_eq!(both_ways.has_path, 1);
+        assert_eq!(both_ways.g_score[295], one_way.g_score[295]);
+        assert_eq!(both_ways.path().len(), one_way.path().len());
+        assert_eq!(both_ways.is_path(6, 5), 0);
+        assert_eq!(both_ways.is_path(20, 11), 0);
+
+        /* Steps pay for the cell they enter, which the backward search leaves */
+        let costs = [1, 1, 2, 1, 1, 1];
+        let mut both_ways = HeapPathFinder::new(3, 2).unwrap();
+        both_ways.set_end(2, 0).unwrap();
+        let mut terrain = FnMap::new(
+            |_col, _row| true,
+            |col, row| costs[(row * 3 + col) as usize],
+        );
+        assert_eq!(both_ways.find_bidirectional_in(&mut terrain), Ok(true));
+        assert_eq!(both_ways.g_score[2], 3);
+        assert_eq!(both_ways.path(), [[0, 0], [1, 0], [2, 0]]);
+
+        let mut rng = SmallRng::seed_from_u64(14);
+        for &movement in &[Movement::FourConnected, Movement::EightConnected] {
+            for _map in 0..20 {
+                let mut path_finder = random_grid(&mut rng, [40, 30], movement, [3, 10], &[]);
+                path_finder.corner_cutting = CornerCutting::Never;
+                let [_, end] = random_endpoints(&mut rng, &mut path_finder);
+                let passable: Vec<bool> = path_finder
+                    .state
+                    .iter()
+                    .map(|&state| state & 0x1 != 0)
+                    .collect();
+                let costs: Vec<i32> = (0..40 * 30).map(|_| rng.gen_range(1, 4)).collect();
+
+                let found = path_finder
+                    .find_bidirectional_in(&mut FnMap::new(
+                        |col, row| passable[(row * 40 + col) as usize],
+                        |col, row| costs[(row * 40 + col) as usize],
+                    ))
+                    .unwrap();
+                if let Some(one_way) = cross_check(&path_finder, found, &costs) {
+                    assert_eq!(path_finder.g_score[end], one_way.g_score[end]);
+                    assert_eq!(path_cost(&path_finder, &costs), one_way.g_score[end]);
+                }
+            }
+        }
+    }
+}
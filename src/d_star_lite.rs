@@ -0,0 +1,339 @@
+use crate::{
+    map::{Callbacks, MapCallbacks, RawCallbacks},
+    path_finder_reconstruct_path, Heuristic, Map, PathFinderBase, PathFinderError, Storage,
+};
+use std::{cmp::Reverse, collections::BinaryHeap, mem, os::raw::c_void};
+
+const INFINITY: i32 = i32::MAX;
+
+/// An incremental planner after D* Lite, searching from end back to start so that the search
+/// tree survives the start moving along the path. Tell it about every cell whose passability or
+/// cost changed through [`cell_changed`](Self::cell_changed), and the next
+/// [`plan`](Self::plan) only repairs the part of the tree the change affects.
+#[derive(Debug, Clone)]
+pub struct DStarLite<S: Storage> {
+    /// The grid being planned over. Its path marks, `parents`, `g_score` and `has_path` hold the
+    /// result of the last plan, as after [`find`](PathFinderBase::find).
+    pub path_finder: PathFinderBase<S>,
+    /* The cost from every cell to end, and its one-step lookahead */
+    g_score: Vec<i32>,
+    rhs: Vec<i32>,
+    /* Cells may be pushed again with a new key, the stale entries no longer match `keys` */
+    open: BinaryHeap<Reverse<([i32; 2], usize)>>,
+    keys: Vec<Option<[i32; 2]>>,
+    key_modifier: i32,
+    last_start: usize,
+    /* Cells changed since the last plan */
+    changed: Vec<usize>,
+    expanded: usize,
+}
+
+impl<S: Storage> DStarLite<S> {
+    /// Starts planning from the start to the end of `path_finder`.
+    pub fn new(path_finder: PathFinderBase<S>) -> Result<Self, PathFinderError> {
+        path_finder.check_endpoints()?;
+
+        let size = path_finder.size();
+        let (start, end) = (path_finder.start as usize, path_finder.end as usize);
+        let mut planner = DStarLite {
+            path_finder,
+            g_score: vec![INFINITY; size],
+            rhs: vec![INFINITY; size],
+            open: BinaryHeap::new(),
+            keys: vec![None; size],
+            key_modifier: 0,
+            last_start: start,
+            changed: Vec::new(),
+            expanded: 0,
+        };
+        planner.rhs[end] = 0;
+        planner.push(end);
+        Ok(planner)
+    }
+
+    /// The number of cells the last plan expanded.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Plans from the current start to end, reusing what still holds of the previous plans.
+    /// Returns whether a path exists.
+    pub fn plan(&mut self, data: *mut c_void) -> bool {
+        self.plan_by(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
+
+    /// Like [`plan`](Self::plan), taking the traversal costs from `map`. Passability still comes
+    /// from the state of [`path_finder`](Self::path_finder).
+    pub fn plan_in<M: Map + ?Sized>(&mut self, map: &mut M) -> bool {
+        self.plan_by(&mut MapCallbacks(map))
+    }
+
+    /// Moves the start, typically to the next cell of the path once a unit has walked there.
+    pub fn set_start(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        self.path_finder.set_start(col, row)?;
+
+        let start = self.path_finder.start as usize;
+        self.key_modifier += self.estimate(self.last_start, start);
+        self.last_start = start;
+        Ok(())
+    }
+
+    /// Schedules the cells around (col, row) for repair after its passability or cost changed,
+    /// as through [`set_passable`](PathFinderBase::set_passable).
+    pub fn cell_changed(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        let index = self.path_finder.cell_index(col, row)?;
+        self.changed.push(index);
+        Ok(())
+    }
+
+    fn plan_by<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> bool {
+        self.expanded = 0;
+        for index in mem::take(&mut self.changed) {
+            self.update_vertex(callbacks, index);
+            for (neighbor, _) in self.neighbors(index) {
+                self.update_vertex(callbacks, neighbor);
+            }
+        }
+
+        let start = self.path_finder.start as usize;
+        while let Some(&Reverse((key, index))) = self.open.peek() {
+            if self.keys[index] != Some(key) {
+                self.open.pop();
+                continue;
+            }
+            if key >= self.key(start) && self.rhs[start] == self.g_score[start] {
+                break;
+            }
+
+            self.open.pop();
+            self.keys[index] = None;
+            self.expanded += 1;
+
+            let new_key = self.key(index);
+            if key < new_key {
+                self.push(index);
+            } else if self.g_score[index] > self.rhs[index] {
+                self.g_score[index] = self.rhs[index];
+                for (neighbor, _) in self.neighbors(index) {
+                    self.update_vertex(callbacks, neighbor);
+                }
+            } else {
+                self.g_score[index] = INFINITY;
+                self.update_vertex(callbacks, index);
+                for (neighbor, _) in self.neighbors(index) {
+                    self.update_vertex(callbacks, neighbor);
+                }
+            }
+        }
+
+        self.write_path(callbacks)
+    }
+
+    /* Recomputes the lookahead of the cell at `index` from its successors */
+    fn update_vertex<C: Callbacks<S>>(&mut self, callbacks: &mut C, index: usize) {
+        if index != self.path_finder.end as usize {
+            self.rhs[index] = self
+                .successors(callbacks, index)
+                .into_iter()
+                .map(|(successor, cost)| cost.saturating_add(self.g_score[successor]))
+                .min()
+                .unwrap_or(INFINITY);
+        }
+
+        self.keys[index] = None;
+        if self.g_score[index] != self.rhs[index] {
+            self.push(index);
+        }
+    }
+
+    fn push(&mut self, index: usize) {
+        let key = self.key(index);
+        self.keys[index] = Some(key);
+        self.open.push(Reverse((key, index)));
+    }
+
+    fn key(&self, index: usize) -> [i32; 2] {
+        let g_score = self.g_score[index].min(self.rhs[index]);
+        let start = self.path_finder.start as usize;
+        [
+            g_score
+                .saturating_add(self.estimate(start, index))
+                .saturating_add(self.key_modifier),
+            g_score,
+        ]
+    }
+
+    fn estimate(&self, from: usize, to: usize) -> i32 {
+        let path_finder = &self.path_finder;
+        path_finder.builtin_heuristic().estimate(
+            path_finder.position(from),
+            path_finder.position(to),
+            path_finder.step_costs(),
+        )
+    }
+
+    /* The cells a step away from the cell at `index`, with the step cost, whatever their state */
+    fn neighbors(&self, index: usize) -> Vec<(usize, i32)> {
+        let [col, row] = self.path_finder.position(index);
+        self.path_finder
+            .neighbor_offsets()
+            .iter()
+            .filter_map(|&offset| self.path_finder.neighbor(col, row, offset))
+            .collect()
+    }
+
+    /* The passable cells a step away from the passable cell at `index`, with the cost of going
+     * there */
+    fn successors<C: Callbacks<S>>(
+        &mut self,
+        callbacks: &mut C,
+        index: usize,
+    ) -> Vec<(usize, i32)> {
+        if self.path_finder.state[index] & 0x1 == 0 {
+            return Vec::new();
+        }
+
+        let mut successors = self.neighbors(index);
+        successors.retain(|&(successor, _)| self.path_finder.state[successor] & 0x1 != 0);
+        for (successor, cost) in &mut successors {
+            let [col, row] = self.path_finder.position(*successor);
+            *cost *= callbacks.step_cost(&mut self.path_finder, col, row);
+        }
+        successors
+    }
+
+    /* Follows the cheapest successors from start to end into the path finder */
+    fn write_path<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> bool {
+        let path_finder = &mut self.path_finder;
+        for index in 0..path_finder.size() {
+            path_finder.state[index] &= !0x8;
+        }
+
+        let (start, end) = (path_finder.start as usize, path_finder.end as usize);
+        if self.g_score[start] == INFINITY {
+            self.path_finder.has_path = 0;
+            return false;
+        }
+
+        /* Every step lowers the g_score, so the walk ends at end or where the tree is broken */
+        self.path_finder.g_score[start] = 0;
+        let mut current = start;
+        while current != end {
+            let g_score = self.g_score[current];
+            let next = self
+                .successors(callbacks, current)
+                .into_iter()
+                .filter(|&(successor, _)| self.g_score[successor] < g_score)
+                .min_by_key(|&(successor, cost)| {
+                    (
+                        cost.saturating_add(self.g_score[successor]),
+                        self.estimate(successor, end),
+                    )
+                });
+            let (next, cost) = match next {
+                Some(successor) => successor,
+                None => {
+                    self.path_finder.has_path = 0;
+                    return false;
+                }
+            };
+            self.path_finder.parents[next] = current as i32;
+            self.path_finder.g_score[next] = self.path_finder.g_score[current] + cost;
+            current = next;
+        }
+
+        path_finder_reconstruct_path(&mut self.path_finder);
+        self.path_finder.has_path = 1;
+        self.path_finder.path_weight = 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{cross_check, path_cost, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    #[test]
+    fn d_star_lite() {
+        let mut rng = SmallRng::seed_from_u64(17);
+        let mut path_finder = random_grid(
+            &mut rng,
+            [60, 40],
+            Movement::EightConnected,
+            [1, 5],
+            &[[0, 0], [59, 39]],
+        );
+        path_finder.set_end(59, 39).unwrap();
+        let mut costs = vec![1; 60 * 40];
+
+        let mut planner = DStarLite::new(path_finder).unwrap();
+        assert!(planner.plan(null_mut()));
+        let optimal = cross_check(&planner.path_finder, true, &costs).unwrap();
+        assert_eq!(planner.path_finder.g_score[2399], optimal.g_score[2399]);
+
+        /* Walking along the path without changes leaves nothing to repair */
+        let [col, row] = planner.path_finder.path()[1];
+        planner.set_start(col, row).unwrap();
+        assert!(planner.plan(null_mut()));
+        assert_eq!(planner.expanded(), 0);
+
+        let (mut repaired, mut from_scratch) = (0, 0);
+        for _change in 0..30 {
+            let path = planner.path_finder.path();
+            if path.len() < 3 {
+                break;
+            }
+
+            /* Walk one step, then block the path a few steps ahead or make it costlier */
+            let [col, row] = path[1];
+            planner.set_start(col, row).unwrap();
+            let [col, row] = path[rng.gen_range(2, path.len().min(8))];
+            if [col, row] != [59, 39] && rng.gen() {
+                planner.path_finder.set_passable(col, row, false).unwrap();
+            } else {
+                costs[(row * 60 + col) as usize] += 5;
+            }
+            planner.cell_changed(col, row).unwrap();
+
+            let mut terrain = FnMap::new(
+                |_col, _row| true,
+                |col, row| costs[(row * 60 + col) as usize],
+            );
+            let found = planner.plan_in(&mut terrain);
+
+            /* The repair expands a fraction of what planning from scratch does */
+            let mut fresh = DStarLite::new(planner.path_finder.clone()).unwrap();
+            assert_eq!(fresh.plan_in(&mut terrain), found);
+            repaired += planner.expanded();
+            from_scratch += fresh.expanded();
+
+            let optimal = match cross_check(&planner.path_finder, found, &costs) {
+                Some(optimal) => optimal,
+                None => break,
+            };
+            assert_eq!(planner.path_finder.g_score[2399], optimal.g_score[2399]);
+            assert_eq!(
+                path_cost(&planner.path_finder, &costs),
+                optimal.g_score[2399]
+            );
+        }
+        assert!(repaired * 5 < from_scratch);
+    }
+
+    #[test]
+    fn d_star_lite_zero_costs() {
+        let mut path_finder = HeapPathFinder::new(4, 1).unwrap();
+        path_finder.set_end(3, 0).unwrap();
+        let mut planner = DStarLite::new(path_finder).unwrap();
+        assert!(planner.plan_in(&mut FnMap::new(|_col, _row| true, |_col, _row| 0)));
+        assert_eq!(planner.path_finder.path(), [[0, 0], [1, 0], [2, 0], [3, 0]]);
+        assert_eq!(planner.path_finder.g_score[3], 3);
+    }
+}
@@ -0,0 +1,54 @@
+use std::{error::Error, fmt, os::raw::c_int};
+
+/// The status returned by the C entry points when no error occurred.
+pub const PATH_FINDER_OK: c_int = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum PathFinderError {
+    /// A column, row or cell index outside of the grid.
+    OutOfBounds = 1,
+    /// A grid with no columns or no rows.
+    InvalidSize,
+    /// A grid with more cells than the storage holds or than an `i32` can index.
+    GridTooLarge,
+    /// Filling the grid without a `fill_func`.
+    MissingFillFunc,
+    /// Searching from a start cell that is a wall.
+    StartImpassable,
+    /// Searching towards an end cell that is a wall.
+    EndImpassable,
+    /// Text or data that does not follow the expected file format.
+    InvalidFormat,
+}
+
+impl PathFinderError {
+    /// The status code returned for this error by the C entry points.
+    pub fn code(self) -> c_int {
+        self as c_int
+    }
+}
+
+impl fmt::Display for PathFinderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            PathFinderError::OutOfBounds => "coordinates out of the grid",
+            PathFinderError::InvalidSize => "grid without columns or rows",
+            PathFinderError::GridTooLarge => "grid too large",
+            PathFinderError::MissingFillFunc => "missing fill function",
+            PathFinderError::StartImpassable => "start cell is impassable",
+            PathFinderError::EndImpassable => "end cell is impassable",
+            PathFinderError::InvalidFormat => "invalid file format",
+        };
+        f.write_str(message)
+    }
+}
+
+impl Error for PathFinderError {}
+
+pub(crate) fn status(result: Result<(), PathFinderError>) -> c_int {
+    match result {
+        Ok(()) => PATH_FINDER_OK,
+        Err(err) => err.code(),
+    }
+}
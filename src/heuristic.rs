@@ -0,0 +1,98 @@
+/// The cost of a single step on the grid, as configured on the path finder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepCosts {
+    pub orthogonal: i32,
+    pub diagonal: i32,
+}
+
+/// An estimate of the cost of reaching `goal` from `cell`, both given as `[col, row]`.
+///
+/// The search only returns least-cost paths when the estimate never exceeds the real cost.
+pub trait Heuristic {
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32;
+}
+
+impl<F> Heuristic for F
+where
+    F: Fn([i32; 2], [i32; 2], StepCosts) -> i32,
+{
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32 {
+        self(cell, goal, costs)
+    }
+}
+
+fn distances(cell: [i32; 2], goal: [i32; 2]) -> (i32, i32) {
+    ((cell[0] - goal[0]).abs(), (cell[1] - goal[1]).abs())
+}
+
+/// Orthogonal steps only, for four-connected movement.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Manhattan;
+
+impl Heuristic for Manhattan {
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32 {
+        let (dx, dy) = distances(cell, goal);
+        costs.orthogonal * (dx + dy)
+    }
+}
+
+/// Every step priced as the cheapest one, for eight-connected movement.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl Heuristic for Chebyshev {
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32 {
+        let (dx, dy) = distances(cell, goal);
+        costs.orthogonal.min(costs.diagonal) * dx.max(dy)
+    }
+}
+
+/// Diagonal steps first, then orthogonal ones, for eight-connected movement.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Octile;
+
+impl Heuristic for Octile {
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32 {
+        let (dx, dy) = distances(cell, goal);
+
+        /* A diagonal step is never worth more than the two orthogonal steps it replaces */
+        let diagonal = costs.diagonal.min(costs.orthogonal * 2);
+        costs.orthogonal * (dx + dy) + (diagonal - costs.orthogonal * 2) * dx.min(dy)
+    }
+}
+
+/// Straight-line distance, admissible when a diagonal step costs at least √2 orthogonal ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Euclidean;
+
+impl Heuristic for Euclidean {
+    fn estimate(&self, cell: [i32; 2], goal: [i32; 2], costs: StepCosts) -> i32 {
+        let (dx, dy) = distances(cell, goal);
+        let distance = f64::from(dx * dx + dy * dy).sqrt();
+        (distance * f64::from(costs.orthogonal)) as i32
+    }
+}
+
+/// No estimate at all, turning the search into Dijkstra's algorithm.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Zero;
+
+impl Heuristic for Zero {
+    fn estimate(&self, _cell: [i32; 2], _goal: [i32; 2], _costs: StepCosts) -> i32 {
+        0
+    }
+}
+
+/// The built-in heuristic used by a path finder without a `heuristic_func`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum HeuristicKind {
+    /// [`Manhattan`] for four-connected movement, [`Octile`] for eight-connected movement.
+    #[default]
+    Auto,
+    Manhattan,
+    Chebyshev,
+    Octile,
+    Euclidean,
+    Zero,
+}
@@ -0,0 +1,690 @@
+use crate::{
+    path_finder_reconstruct_path, HeapPathFinder, Movement, PathFinderBase, PathFinderError,
+    Storage,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    ptr::null_mut,
+};
+
+/// A hierarchical planner after HPA*. The grid is cut into square clusters, the passable runs
+/// along every border between two clusters get an entrance, and the costs between the entrances
+/// of a cluster are precomputed. Searches then run over entrances only, and the steps inside a
+/// cluster are refined with a plain search of that cluster.
+///
+/// Clusters are entered and left through orthogonal steps, or through diagonal ones where corner
+/// cutting squeezes between two walls. Cell costs are ignored, so paths may be a little longer
+/// than the ones of [`find`](PathFinderBase::find).
+#[derive(Debug, Clone)]
+pub struct Hierarchy<S: Storage> {
+    /// The full-resolution grid the clusters are cut from. Walls changed here are picked up
+    /// through [`cell_changed`](Self::cell_changed). After [`find`](Self::find), only the cells
+    /// along the refined path carry `parents` and `g_score`.
+    pub path_finder: PathFinderBase<S>,
+    cluster_size: i32,
+    cluster_cols: i32,
+    cluster_rows: i32,
+    /* The pairs of cells stepping across every border, by the clusters on both sides, and the
+     * cells across from every entrance */
+    transitions: HashMap<[usize; 2], Vec<[usize; 2]>>,
+    crossings: HashMap<usize, Vec<usize>>,
+    /* The costs between the entrances of every cluster */
+    edges: Vec<HashMap<usize, Vec<(usize, i32)>>>,
+    dirty: HashSet<usize>,
+    rebuilds: usize,
+}
+
+impl<S: Storage> Hierarchy<S> {
+    /// Builds the abstraction of `path_finder` with clusters of `cluster_size` by
+    /// `cluster_size` cells.
+    pub fn new(path_finder: PathFinderBase<S>, cluster_size: i32) -> Result<Self, PathFinderError> {
+        path_finder.check_size()?;
+        if cluster_size < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+
+        let cluster_cols = (path_finder.cols + cluster_size - 1) / cluster_size;
+        let cluster_rows = (path_finder.rows + cluster_size - 1) / cluster_size;
+        let clusters = (cluster_cols * cluster_rows) as usize;
+        let mut hierarchy = Hierarchy {
+            path_finder,
+            cluster_size,
+            cluster_cols,
+            cluster_rows,
+            transitions: HashMap::new(),
+            crossings: HashMap::new(),
+            edges: vec![HashMap::new(); clusters],
+            dirty: (0..clusters).collect(),
+            rebuilds: 0,
+        };
+        for cluster in 0..clusters {
+            hierarchy.scan_borders(cluster);
+        }
+        hierarchy.rebuild();
+        Ok(hierarchy)
+    }
+
+    /// The number of times a cluster had its entrance costs computed.
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds
+    }
+
+    /// Schedules the cluster of (col, row) for a rebuild after its passability changed, as
+    /// through [`set_passable`](PathFinderBase::set_passable). A cell on the border of a cluster
+    /// also changes the entrances of the cluster across.
+    pub fn cell_changed(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        self.path_finder.cell_index(col, row)?;
+
+        /* The clusters across the sides and corners of the cluster the cell lies on */
+        let cluster = self.cluster_of([col, row]);
+        let [cluster_col, cluster_row] = self.cluster_position(cluster);
+        let (x, y) = (col % self.cluster_size, row % self.cluster_size);
+        let last = self.cluster_size - 1;
+        let on_side = |offset: i32, position: i32| match offset {
+            -1 => position == 0,
+            1 => position == last,
+            _ => true,
+        };
+        let mut neighbors = Vec::new();
+        for &dx in &[-1, 0, 1] {
+            for &dy in &[-1, 0, 1] {
+                let (neighbor_col, neighbor_row) = (cluster_col + dx, cluster_row + dy);
+                if [dx, dy] != [0, 0]
+                    && on_side(dx, x)
+                    && on_side(dy, y)
+                    && (0..self.cluster_cols).contains(&neighbor_col)
+                    && (0..self.cluster_rows).contains(&neighbor_row)
+                {
+                    neighbors.push((neighbor_row * self.cluster_cols + neighbor_col) as usize);
+                }
+            }
+        }
+
+        if !neighbors.is_empty() {
+            self.scan_borders(cluster);
+        }
+        self.dirty.insert(cluster);
+        self.dirty.extend(neighbors);
+        Ok(())
+    }
+
+    /// Searches from the start to the end of [`path_finder`](Self::path_finder) over the
+    /// entrances, then refines the path inside every cluster it crosses. Both steps only count
+    /// `orthogonal_cost` and `diagonal_cost`: `cost_func`, `score_func` and `heuristic_func` are
+    /// ignored.
+    ///
+    /// Walls changed without [`cell_changed`](Self::cell_changed) may leave the abstraction
+    /// crossing them, in which case no path is found.
+    pub fn find(&mut self) -> Result<bool, PathFinderError> {
+        self.path_finder.check_endpoints()?;
+        /* Drop the path marks, parents and scores of the last search */
+        self.path_finder.clear_path();
+        self.rebuild();
+
+        let start = self.path_finder.start as usize;
+        let end = self.path_finder.end as usize;
+        let start_cluster = self.cluster_of(self.path_finder.position(start));
+        let end_cluster = self.cluster_of(self.path_finder.position(end));
+
+        /* Links start and end to the entrances of their clusters, and to each other */
+        let mut links: HashMap<usize, Vec<(usize, i32)>> = HashMap::new();
+        let from_start = self.local_distances(start_cluster, start);
+        let to_end = self.local_distances(end_cluster, end);
+        for (&entrance, _) in self.edges[start_cluster].iter() {
+            if let Some(cost) = from_start(entrance) {
+                links.entry(start).or_default().push((entrance, cost));
+            }
+        }
+        for (&entrance, _) in self.edges[end_cluster].iter() {
+            if let Some(cost) = to_end(entrance) {
+                links.entry(entrance).or_default().push((end, cost));
+            }
+        }
+        if start_cluster == end_cluster {
+            if let Some(cost) = from_start(end) {
+                links.entry(start).or_default().push((end, cost));
+            }
+        }
+
+        let waypoints = match self.abstract_search(start, end, &links) {
+            Some(waypoints) => waypoints,
+            None => return Ok(false),
+        };
+
+        self.path_finder.g_score[start] = 0;
+        for pair in waypoints.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let from_cluster = self.cluster_of(self.path_finder.position(from));
+            let linked = if from_cluster == self.cluster_of(self.path_finder.position(to)) {
+                self.refine(from_cluster, from, to)
+            } else {
+                self.cross(from, to)
+            };
+            if !linked {
+                return Ok(false);
+            }
+        }
+
+        path_finder_reconstruct_path(&mut self.path_finder);
+        self.path_finder.has_path = 1;
+        self.path_finder.path_weight = 1.0;
+        Ok(true)
+    }
+
+    fn cluster_of(&self, [col, row]: [i32; 2]) -> usize {
+        ((row / self.cluster_size) * self.cluster_cols + col / self.cluster_size) as usize
+    }
+
+    fn cluster_position(&self, cluster: usize) -> [i32; 2] {
+        let cluster = cluster as i32;
+        [cluster % self.cluster_cols, cluster / self.cluster_cols]
+    }
+
+    /* The first cell and the size of the cluster */
+    fn cluster_bounds(&self, cluster: usize) -> ([i32; 2], [i32; 2]) {
+        let [cluster_col, cluster_row] = self.cluster_position(cluster);
+        let origin = [
+            cluster_col * self.cluster_size,
+            cluster_row * self.cluster_size,
+        ];
+        let size = [
+            self.cluster_size.min(self.path_finder.cols - origin[0]),
+            self.cluster_size.min(self.path_finder.rows - origin[1]),
+        ];
+        (origin, size)
+    }
+
+    /* Finds the transitions across the right and bottom borders of `cluster` and of the clusters
+     * on its left and top, one in the middle of every run of cells passable on both sides, and
+     * across the corners around it. Diagonal steps get a transition of their own where they cut
+     * between two walls, as no orthogonal steps can replace them */
+    fn scan_borders(&mut self, cluster: usize) {
+        let [cluster_col, cluster_row] = self.cluster_position(cluster);
+        let columns = self.cluster_cols as usize;
+        let mut borders = Vec::new();
+        if cluster_col > 0 {
+            borders.push(cluster - 1);
+        }
+        if cluster_row > 0 {
+            borders.push(cluster - columns);
+        }
+        borders.push(cluster);
+
+        for from in borders {
+            let [from_col, from_row] = self.cluster_position(from);
+            let (origin, size) = self.cluster_bounds(from);
+            let [last_col, last_row] = [origin[0] + size[0] - 1, origin[1] + size[1] - 1];
+            if from_col < self.cluster_cols - 1 {
+                let col = last_col;
+                let pairs = (origin[1]..=last_row).map(|row| {
+                    [
+                        self.path_finder.index(col, row),
+                        self.path_finder.index(col + 1, row),
+                    ]
+                });
+                let mut transitions = self.runs(pairs);
+                for row in origin[1]..last_row {
+                    transitions.extend(self.squeeze([col, row], [col + 1, row + 1]));
+                    transitions.extend(self.squeeze([col, row + 1], [col + 1, row]));
+                }
+                self.set_transitions([from, from + 1], transitions);
+            }
+            if from_row < self.cluster_rows - 1 {
+                let row = last_row;
+                let pairs = (origin[0]..=last_col).map(|col| {
+                    [
+                        self.path_finder.index(col, row),
+                        self.path_finder.index(col, row + 1),
+                    ]
+                });
+                let mut transitions = self.runs(pairs);
+                for col in origin[0]..last_col {
+                    transitions.extend(self.squeeze([col, row], [col + 1, row + 1]));
+                    transitions.extend(self.squeeze([col + 1, row], [col, row + 1]));
+                }
+                self.set_transitions([from, from + columns], transitions);
+            }
+        }
+
+        /* The corners are named after the cluster on their top left */
+        for &[x, y] in &[[-1, -1], [0, -1], [-1, 0], [0, 0]] {
+            let [from_col, from_row] = [cluster_col + x, cluster_row + y];
+            if from_col < 0
+                || from_row < 0
+                || from_col >= self.cluster_cols - 1
+                || from_row >= self.cluster_rows - 1
+            {
+                continue;
+            }
+
+            let from = (from_row * self.cluster_cols + from_col) as usize;
+            let ([col, row], _) = self.cluster_bounds(from + columns + 1);
+            let down = self.squeeze([col - 1, row - 1], [col, row]);
+            let up = self.squeeze([col, row - 1], [col - 1, row]);
+            self.set_transitions([from, from + columns + 1], down.into_iter().collect());
+            self.set_transitions([from + 1, from + columns], up.into_iter().collect());
+        }
+    }
+
+    /* Replaces the transitions between two clusters, keeping the crossings in step */
+    fn set_transitions(&mut self, clusters: [usize; 2], transitions: Vec<[usize; 2]>) {
+        for [from, to] in self.transitions.remove(&clusters).unwrap_or_default() {
+            for &(cell, other) in &[(from, to), (to, from)] {
+                let across = self.crossings.get_mut(&cell).unwrap();
+                across.retain(|&known| known != other);
+                if across.is_empty() {
+                    self.crossings.remove(&cell);
+                }
+            }
+        }
+        for &[from, to] in &transitions {
+            self.crossings.entry(from).or_default().push(to);
+            self.crossings.entry(to).or_default().push(from);
+        }
+        self.transitions.insert(clusters, transitions);
+    }
+
+    /* The diagonal step from `from` to `to` if the corner cutting lets it pass between walls */
+    fn squeeze(&self, from: [i32; 2], to: [i32; 2]) -> Option<[usize; 2]> {
+        let path_finder = &self.path_finder;
+        let passable = |[col, row]: [i32; 2]| path_finder.state[path_finder.index(col, row)] & 0x1;
+        if path_finder.movement != Movement::EightConnected
+            || passable(from) == 0
+            || passable(to) == 0
+            || passable([to[0], from[1]]) != 0
+            || passable([from[0], to[1]]) != 0
+        {
+            return None;
+        }
+
+        let (to, _) = path_finder.neighbor(from[0], from[1], [to[0] - from[0], to[1] - from[1]])?;
+        Some([path_finder.index(from[0], from[1]), to])
+    }
+
+    fn runs(&self, pairs: impl Iterator<Item = [usize; 2]>) -> Vec<[usize; 2]> {
+        let mut transitions = Vec::new();
+        let mut run = Vec::new();
+        for pair in pairs.map(Some).chain(Some(None)) {
+            match pair {
+                Some(pair)
+                    if pair
+                        .iter()
+                        .all(|&cell| self.path_finder.state[cell] & 0x1 != 0) =>
+                {
+                    run.push(pair)
+                }
+                _ => {
+                    if !run.is_empty() {
+                        transitions.push(run[run.len() / 2]);
+                        run.clear();
+                    }
+                }
+            }
+        }
+        transitions
+    }
+
+    /* Recomputes the costs between the entrances of every dirty cluster */
+    fn rebuild(&mut self) {
+        let mut dirty: Vec<_> = self.dirty.drain().collect();
+        dirty.sort_unstable();
+        for cluster in dirty {
+            /* Entrances lie on the outline of their cluster */
+            let (origin, size) = self.cluster_bounds(cluster);
+            let [last_col, last_row] = [origin[0] + size[0] - 1, origin[1] + size[1] - 1];
+            let entrances: Vec<usize> = (origin[1]..=last_row)
+                .flat_map(|row| (origin[0]..=last_col).map(move |col| [col, row]))
+                .filter(|&[col, row]| {
+                    col == origin[0] || col == last_col || row == origin[1] || row == last_row
+                })
+                .map(|[col, row]| self.path_finder.index(col, row))
+                .filter(|cell| self.crossings.contains_key(cell))
+                .collect();
+
+            let mut edges = HashMap::new();
+            for &entrance in &entrances {
+                let distances = self.local_distances(cluster, entrance);
+                let reachable = entrances
+                    .iter()
+                    .filter(|&&other| other != entrance)
+                    .filter_map(|&other| Some((other, distances(other)?)))
+                    .collect();
+                edges.insert(entrance, reachable);
+            }
+            self.edges[cluster] = edges;
+            self.rebuilds += 1;
+        }
+    }
+
+    /* A path finder over the cells of `cluster` only, with the settings of the whole grid */
+    fn local(&self, cluster: usize) -> (HeapPathFinder, [i32; 2]) {
+        let (origin, [cols, rows]) = self.cluster_bounds(cluster);
+        let mut local = HeapPathFinder::new(cols, rows).unwrap();
+        local.movement = self.path_finder.movement;
+        local.orthogonal_cost = self.path_finder.orthogonal_cost;
+        local.diagonal_cost = self.path_finder.diagonal_cost;
+        local.corner_cutting = self.path_finder.corner_cutting;
+        local.heuristic = self.path_finder.heuristic;
+        local
+            .fill_with(|col, row| {
+                let index = self.path_finder.index(origin[0] + col, origin[1] + row);
+                self.path_finder.state[index] & 0x1 != 0
+            })
+            .unwrap();
+        (local, origin)
+    }
+
+    /* The distances from `cell` to the cells of its cluster, staying inside it */
+    fn local_distances(&self, cluster: usize, cell: usize) -> impl Fn(usize) -> Option<i32> {
+        let (mut local, origin) = self.local(cluster);
+        let [col, row] = self.path_finder.position(cell);
+        local.set_start(col - origin[0], row - origin[1]).unwrap();
+        let reached = local.distance_map(null_mut()).is_ok();
+
+        let cols = self.path_finder.cols;
+        move |other| {
+            let other = other as i32;
+            let distance = local.distance(other % cols - origin[0], other / cols - origin[1]);
+            distance.filter(|_| reached)
+        }
+    }
+
+    /* Links `to` back to `from` through the cells of their cluster, if they are still linked */
+    fn refine(&mut self, cluster: usize, from: usize, to: usize) -> bool {
+        let (mut local, origin) = self.local(cluster);
+        let [col, row] = self.path_finder.position(from);
+        let [to_col, to_row] = self.path_finder.position(to);
+        local.set_start(col - origin[0], row - origin[1]).unwrap();
+        local
+            .set_end(to_col - origin[0], to_row - origin[1])
+            .unwrap();
+        if local.find(null_mut()) != Ok(true) {
+            return false;
+        }
+
+        let mut previous = from;
+        for [col, row] in local.path().into_iter().skip(1) {
+            let cell = self.path_finder.index(origin[0] + col, origin[1] + row);
+            let step = self.path_finder.g_score[from] + local.g_score[local.index(col, row)];
+            self.path_finder.parents[cell] = previous as i32;
+            self.path_finder.g_score[cell] = step;
+            previous = cell;
+        }
+        true
+    }
+
+    /* Links `to` back to `from` across the border of their clusters, if the step is still
+     * possible */
+    fn cross(&mut self, from: usize, to: usize) -> bool {
+        let [col, row] = self.path_finder.position(from);
+        let [to_col, to_row] = self.path_finder.position(to);
+        let offset = [to_col - col, to_row - row];
+        let step_cost = match self.path_finder.neighbor(col, row, offset) {
+            Some((_, step_cost)) if self.path_finder.state[to] & 0x1 != 0 => step_cost,
+            _ => return false,
+        };
+        self.path_finder.parents[to] = from as i32;
+        self.path_finder.g_score[to] = self.path_finder.g_score[from] + step_cost;
+        true
+    }
+
+    /* A* over the entrances, with the extra links of start and end */
+    fn abstract_search(
+        &self,
+        start: usize,
+        end: usize,
+        links: &HashMap<usize, Vec<(usize, i32)>>,
+    ) -> Option<Vec<usize>> {
+        let path_finder = &self.path_finder;
+        let heuristic = path_finder.builtin_heuristic();
+        let goal = path_finder.position(end);
+        let estimate = |cell: usize| {
+            heuristic.estimate(path_finder.position(cell), goal, path_finder.step_costs())
+        };
+
+        let mut g_scores = HashMap::new();
+        let mut parents = HashMap::new();
+        let mut closed = HashSet::new();
+        let mut open = BinaryHeap::new();
+        g_scores.insert(start, 0);
+        open.push(Reverse((estimate(start), start)));
+
+        while let Some(Reverse((_, current))) = open.pop() {
+            if !closed.insert(current) {
+                continue;
+            }
+            if current == end {
+                let mut waypoints = vec![end];
+                while let Some(&parent) = parents.get(waypoints.last().unwrap()) {
+                    waypoints.push(parent);
+                }
+                waypoints.reverse();
+                return Some(waypoints);
+            }
+
+            let g_score = g_scores[&current];
+            let cluster = self.cluster_of(path_finder.position(current));
+            let [col, row] = path_finder.position(current);
+            let crossings = self
+                .crossings
+                .get(&current)
+                .into_iter()
+                .flatten()
+                .filter_map(|&other| {
+                    let [other_col, other_row] = path_finder.position(other);
+                    let (_, cost) =
+                        path_finder.neighbor(col, row, [other_col - col, other_row - row])?;
+                    Some((other, cost))
+                });
+            let successors = self.edges[cluster]
+                .get(&current)
+                .into_iter()
+                .flatten()
+                .copied()
+                .chain(links.get(&current).into_iter().flatten().copied())
+                .chain(crossings);
+
+            for (next, cost) in successors {
+                let next_g_score = g_score + cost;
+                if !closed.contains(&next)
+                    && g_scores
+                        .get(&next)
+                        .map_or(true, |&known| next_g_score < known)
+                {
+                    g_scores.insert(next, next_g_score);
+                    parents.insert(next, current);
+                    open.push(Reverse((next_g_score + estimate(next), next)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{cross_check, path_cost, random_endpoints, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn hierarchy() {
+        let mut rng = SmallRng::seed_from_u64(18);
+        for &movement in &[Movement::FourConnected, Movement::EightConnected] {
+            let mut path_finder = random_grid(&mut rng, [64, 45], movement, [1, 5], &[]);
+            path_finder.corner_cutting = CornerCutting::Never;
+            assert_eq!(
+                Hierarchy::new(path_finder.clone(), 0).unwrap_err(),
+                PathFinderError::InvalidSize
+            );
+
+            let mut hierarchy = Hierarchy::new(path_finder, 10).unwrap();
+            assert_eq!(hierarchy.rebuilds(), 7 * 5);
+            let costs = vec![1; 64 * 45];
+            for change in 0..30 {
+                hierarchy.path_finder.clear_path();
+                let [_, end] = random_endpoints(&mut rng, &mut hierarchy.path_finder);
+                let found = hierarchy.find().unwrap();
+                if let Some(optimal) = cross_check(&hierarchy.path_finder, found, &costs) {
+                    let optimal = optimal.g_score[end];
+                    let cost = hierarchy.path_finder.g_score[end];
+                    /* The detours through entrances stay within about a cluster side */
+                    assert!(cost >= optimal && cost <= optimal + 10 * 10);
+                    assert_eq!(path_cost(&hierarchy.path_finder, &costs), cost);
+
+                    /* Every stretch of the path inside a cluster is refined as short as a
+                     * search of that cluster alone makes it */
+                    let path = hierarchy.path_finder.path();
+                    let cluster_of = |[col, row]: [i32; 2]| [col / 10, row / 10];
+                    for stretch in path.chunk_by(|&from, &to| cluster_of(from) == cluster_of(to)) {
+                        let [first, last] = [stretch[0], stretch[stretch.len() - 1]];
+                        let [cluster_col, cluster_row] = cluster_of(first);
+                        let origin = [cluster_col * 10, cluster_row * 10];
+                        let mut cluster =
+                            HeapPathFinder::new(10.min(64 - origin[0]), 10.min(45 - origin[1]))
+                                .unwrap();
+                        cluster.movement = movement;
+                        cluster.corner_cutting = CornerCutting::Never;
+                        cluster.orthogonal_cost = 10;
+                        cluster.diagonal_cost = 14;
+                        let grid = &hierarchy.path_finder;
+                        cluster
+                            .fill_with(|col, row| {
+                                grid.is_passable(origin[0] + col, origin[1] + row) == 1
+                            })
+                            .unwrap();
+                        cluster
+                            .set_start(first[0] - origin[0], first[1] - origin[1])
+                            .unwrap();
+                        cluster
+                            .set_end(last[0] - origin[0], last[1] - origin[1])
+                            .unwrap();
+                        assert_eq!(cluster.find(null_mut()), Ok(true));
+                        let g_score = |[col, row]: [i32; 2]| grid.g_score[grid.index(col, row)];
+                        assert_eq!(
+                            g_score(last) - g_score(first),
+                            cluster.g_score[cluster.end as usize]
+                        );
+                    }
+                }
+
+                /* A cell inside a cluster, then one on the border between two */
+                let rebuilds = hierarchy.rebuilds();
+                let [col, row] = if change % 2 == 0 { [23, 14] } else { [29, 14] };
+                let passable = hierarchy.path_finder.is_passable(col, row) == 0;
+                hierarchy
+                    .path_finder
+                    .set_passable(col, row, passable)
+                    .unwrap();
+                hierarchy.cell_changed(col, row).unwrap();
+                hierarchy.find().unwrap_or_default();
+                assert_eq!(hierarchy.rebuilds(), rebuilds + 1 + change % 2);
+            }
+        }
+    }
+
+    #[test]
+    fn hierarchy_corner_cutting() {
+        /*
+         * Representation
+         *
+         * /----\
+         * |S #.|
+         * |.#.E|
+         * \----/
+         */
+
+        let mut path_finder = HeapPathFinder::new(4, 2).unwrap();
+        path_finder.movement = Movement::EightConnected;
+        path_finder.set_passable(2, 0, false).unwrap();
+        path_finder.set_passable(1, 1, false).unwrap();
+        path_finder.set_end(3, 1).unwrap();
+        assert_eq!(path_finder.clone().find(null_mut()), Ok(true));
+        let mut hierarchy = Hierarchy::new(path_finder, 2).unwrap();
+        assert_eq!(hierarchy.find(), Ok(true));
+        assert_eq!(
+            hierarchy.path_finder.path(),
+            [[0, 0], [1, 0], [2, 1], [3, 1]]
+        );
+
+        /* Squeezes across sides and corners, kept up to date as cells change */
+        let mut rng = SmallRng::seed_from_u64(18);
+        let path_finder = random_grid(&mut rng, [30, 30], Movement::EightConnected, [2, 5], &[]);
+        let mut hierarchy = Hierarchy::new(path_finder, 3).unwrap();
+        let costs = vec![1; 30 * 30];
+        for _change in 0..60 {
+            hierarchy.path_finder.clear_path();
+            let [_, end] = random_endpoints(&mut rng, &mut hierarchy.path_finder);
+            let found = hierarchy.find().unwrap();
+            if cross_check(&hierarchy.path_finder, found, &costs).is_some() {
+                let cost = path_cost(&hierarchy.path_finder, &costs);
+                assert_eq!(cost, hierarchy.path_finder.g_score[end]);
+            }
+
+            let [col, row] = [rng.gen_range(0, 30), rng.gen_range(0, 30)];
+            let passable = hierarchy.path_finder.is_passable(col, row) == 0;
+            hierarchy
+                .path_finder
+                .set_passable(col, row, passable)
+                .unwrap();
+            hierarchy.cell_changed(col, row).unwrap();
+        }
+    }
+
+    #[test]
+    fn hierarchy_stale_walls() {
+        /*
+         * Representation
+         *
+         * /------\
+         * |S.#..E|
+         * \------/
+         */
+
+        let mut path_finder = HeapPathFinder::new(6, 1).unwrap();
+        path_finder.set_end(5, 0).unwrap();
+        let mut hierarchy = Hierarchy::new(path_finder, 2).unwrap();
+        assert_eq!(hierarchy.find(), Ok(true));
+
+        /* A wall the abstraction was not told about fails the search instead of panicking */
+        hierarchy.path_finder.set_passable(2, 0, false).unwrap();
+        assert_eq!(hierarchy.find(), Ok(false));
+        assert_eq!(hierarchy.path_finder.has_path, 0);
+        hierarchy.cell_changed(2, 0).unwrap();
+        assert_eq!(hierarchy.find(), Ok(false));
+
+        hierarchy.path_finder.set_passable(2, 0, true).unwrap();
+        hierarchy.cell_changed(2, 0).unwrap();
+        assert_eq!(hierarchy.find(), Ok(true));
+        assert_eq!(hierarchy.path_finder.path().len(), 6);
+    }
+
+    #[test]
+    fn hierarchy_repeated_queries() {
+        let mut rng = SmallRng::seed_from_u64(18);
+        let path_finder = random_grid(&mut rng, [24, 24], Movement::FourConnected, [1, 6], &[]);
+        let mut hierarchy = Hierarchy::new(path_finder, 4).unwrap();
+        for _query in 0..20 {
+            /* Without clearing in between, only the last path may be left marked */
+            random_endpoints(&mut rng, &mut hierarchy.path_finder);
+            let mut fresh = Hierarchy::new(hierarchy.path_finder.clone(), 4).unwrap();
+            fresh.path_finder.clear_path();
+            let found = fresh.find().unwrap();
+            assert_eq!(hierarchy.find(), Ok(found));
+            assert_eq!(hierarchy.path_finder.has_path, fresh.path_finder.has_path);
+            assert_eq!(hierarchy.path_finder.state, fresh.path_finder.state);
+            assert_eq!(hierarchy.path_finder.path(), fresh.path_finder.path());
+
+            let marked = (0..hierarchy.path_finder.size())
+                .filter(|&index| hierarchy.path_finder.state[index] & 0x8 != 0)
+                .count();
+            let path = hierarchy.path_finder.path();
+            assert_eq!(marked, path.len().saturating_sub(2));
+        }
+    }
+}
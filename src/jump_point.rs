@@ -0,0 +1,328 @@
+use crate::{
+    path_finder_open_set_is_empty, path_finder_open_set_pop, path_finder_open_set_push,
+    path_finder_reconstruct_path, CornerCutting, Movement, PathFinderBase, PathFinderError,
+    Storage,
+};
+
+/*
+ * Jump Point Search, after the finders of PathFinding.js: one pruning rule per corner cutting
+ * policy, plus one for four-connected grids. Only jump points go through the open set, the cells
+ * between two of them are filled in once the end is reached.
+ */
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Searches from start to end like [`find`](Self::find), expanding only jump points. The
+    /// result is as short as the one of plain A* as long as every cell costs the same, so
+    /// `cost_func`, `score_func` and `heuristic_func` are ignored.
+    pub fn find_jump_points(&mut self) -> Result<bool, PathFinderError> {
+        self.check_endpoints()?;
+        self.begin();
+
+        while path_finder_open_set_is_empty(self) == 0 {
+            let current = path_finder_open_set_pop(self);
+            self.state[current as usize] |= 0x4;
+            if current == self.end {
+                self.fill_jumps();
+                path_finder_reconstruct_path(self);
+                self.has_path = 1;
+                self.path_weight = 1.0;
+                return Ok(true);
+            }
+
+            let cell = self.position(current as usize);
+            for neighbor in self.pruned_neighbors(current as usize) {
+                let direction = [neighbor[0] - cell[0], neighbor[1] - cell[1]];
+                let jump_point = match self.jump(neighbor, direction) {
+                    Some(jump_point) => jump_point,
+                    None => continue,
+                };
+                let index = self.index(jump_point[0], jump_point[1]);
+                if self.state[index] & 0x4 != 0 {
+                    continue;
+                }
+
+                let g_score = self.g_score[current as usize] + self.jump_cost(cell, jump_point);
+                if self.state[index] & 0x2 == 0 || g_score < self.g_score[index] {
+                    self.parents[index] = current;
+                    self.g_score[index] = g_score;
+                    self.f_score[index] =
+                        g_score + self.estimate(self.builtin_heuristic(), index as i32);
+                    path_finder_open_set_push(self, index as i32);
+                }
+            }
+        }
+
+        self.has_path = 0;
+        Ok(false)
+    }
+
+    fn walkable(&self, col: i32, row: i32) -> bool {
+        col >= 0
+            && col < self.cols
+            && row >= 0
+            && row < self.rows
+            && self.state[self.index(col, row)] & 0x1 != 0
+    }
+
+    fn jump_cost(&self, [col, row]: [i32; 2], [to_col, to_row]: [i32; 2]) -> i32 {
+        let (cols, rows) = ((to_col - col).abs(), (to_row - row).abs());
+        let diagonal = cols.min(rows);
+        diagonal * self.diagonal_cost + (cols.max(rows) - diagonal) * self.orthogonal_cost
+    }
+
+    /* The neighbours worth jumping to from the cell at `index`, given the direction it was
+     * reached from */
+    fn pruned_neighbors(&self, index: usize) -> Vec<[i32; 2]> {
+        let [col, row] = self.position(index);
+        if index == self.start as usize {
+            return self
+                .neighbor_offsets()
+                .iter()
+                .filter_map(|&offset| self.neighbor(col, row, offset))
+                .filter(|&(index, _)| self.state[index] & 0x1 != 0)
+                .map(|(index, _)| self.position(index))
+                .collect();
+        }
+
+        let [parent_col, parent_row] = self.position(self.parents[index] as usize);
+        let (dx, dy) = ((col - parent_col).signum(), (row - parent_row).signum());
+        let walkable = |x: i32, y: i32| self.walkable(col + x, row + y);
+        let mut neighbors = Vec::with_capacity(5);
+        let mut push = |condition: bool, x: i32, y: i32| {
+            if condition {
+                neighbors.push([col + x, row + y]);
+            }
+        };
+
+        match (self.movement, self.corner_cutting) {
+            (Movement::FourConnected, _) => {
+                if dx != 0 {
+                    push(walkable(0, -1), 0, -1);
+                    push(walkable(0, 1), 0, 1);
+                    push(walkable(dx, 0), dx, 0);
+                } else {
+                    push(walkable(-1, 0), -1, 0);
+                    push(walkable(1, 0), 1, 0);
+                    push(walkable(0, dy), 0, dy);
+                }
+            }
+            (Movement::EightConnected, CornerCutting::Always) => {
+                if dx != 0 && dy != 0 {
+                    push(walkable(0, dy), 0, dy);
+                    push(walkable(dx, 0), dx, 0);
+                    push(walkable(dx, dy), dx, dy);
+                    push(!walkable(-dx, 0), -dx, dy);
+                    push(!walkable(0, -dy), dx, -dy);
+                } else if dx == 0 {
+                    push(walkable(0, dy), 0, dy);
+                    push(!walkable(1, 0), 1, dy);
+                    push(!walkable(-1, 0), -1, dy);
+                } else {
+                    push(walkable(dx, 0), dx, 0);
+                    push(!walkable(0, 1), dx, 1);
+                    push(!walkable(0, -1), dx, -1);
+                }
+            }
+            (Movement::EightConnected, CornerCutting::OneSideOpen) => {
+                if dx != 0 && dy != 0 {
+                    let (vertical, horizontal) = (walkable(0, dy), walkable(dx, 0));
+                    push(vertical, 0, dy);
+                    push(horizontal, dx, 0);
+                    push(vertical || horizontal, dx, dy);
+                    push(!walkable(-dx, 0) && vertical, -dx, dy);
+                    push(!walkable(0, -dy) && horizontal, dx, -dy);
+                } else if dx == 0 {
+                    if walkable(0, dy) {
+                        push(true, 0, dy);
+                        push(!walkable(1, 0), 1, dy);
+                        push(!walkable(-1, 0), -1, dy);
+                    }
+                } else if walkable(dx, 0) {
+                    push(true, dx, 0);
+                    push(!walkable(0, 1), dx, 1);
+                    push(!walkable(0, -1), dx, -1);
+                }
+            }
+            (Movement::EightConnected, CornerCutting::Never) => {
+                if dx != 0 && dy != 0 {
+                    let (vertical, horizontal) = (walkable(0, dy), walkable(dx, 0));
+                    push(vertical, 0, dy);
+                    push(horizontal, dx, 0);
+                    push(vertical && horizontal, dx, dy);
+                } else if dx == 0 {
+                    let (next, right, left) = (walkable(0, dy), walkable(1, 0), walkable(-1, 0));
+                    push(next, 0, dy);
+                    push(next && right, 1, dy);
+                    push(next && left, -1, dy);
+                    push(right, 1, 0);
+                    push(left, -1, 0);
+                } else {
+                    let (next, bottom, top) = (walkable(dx, 0), walkable(0, 1), walkable(0, -1));
+                    push(next, dx, 0);
+                    push(next && bottom, dx, 1);
+                    push(next && top, dx, -1);
+                    push(bottom, 0, 1);
+                    push(top, 0, -1);
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /* The first jump point met going from (col, row) in `direction`, the cell included */
+    fn jump(&self, [mut col, mut row]: [i32; 2], [dx, dy]: [i32; 2]) -> Option<[i32; 2]> {
+        let end = self.position(self.end as usize);
+        loop {
+            if !self.walkable(col, row) {
+                return None;
+            }
+            if [col, row] == end {
+                return Some(end);
+            }
+
+            let walkable = |x: i32, y: i32| self.walkable(col + x, row + y);
+            let jumps = |directions: [[i32; 2]; 2]| {
+                directions
+                    .iter()
+                    .any(|&[x, y]| self.jump([col + x, row + y], [x, y]).is_some())
+            };
+            let forced = match (self.movement, self.corner_cutting) {
+                (Movement::FourConnected, _) if dx != 0 => {
+                    (walkable(0, -1) && !walkable(-dx, -1)) || (walkable(0, 1) && !walkable(-dx, 1))
+                }
+                (Movement::FourConnected, _) => {
+                    (walkable(-1, 0) && !walkable(-1, -dy))
+                        || (walkable(1, 0) && !walkable(1, -dy))
+                        || jumps([[1, 0], [-1, 0]])
+                }
+                (Movement::EightConnected, _) if dx != 0 && dy != 0 => {
+                    let corner = self.corner_cutting != CornerCutting::Never
+                        && ((walkable(-dx, dy) && !walkable(-dx, 0))
+                            || (walkable(dx, -dy) && !walkable(0, -dy)));
+                    corner || jumps([[dx, 0], [0, dy]])
+                }
+                (Movement::EightConnected, CornerCutting::Never) if dx != 0 => {
+                    (walkable(0, -1) && !walkable(-dx, -1)) || (walkable(0, 1) && !walkable(-dx, 1))
+                }
+                (Movement::EightConnected, CornerCutting::Never) => {
+                    (walkable(-1, 0) && !walkable(-1, -dy)) || (walkable(1, 0) && !walkable(1, -dy))
+                }
+                (Movement::EightConnected, _) if dx != 0 => {
+                    (walkable(dx, 1) && !walkable(0, 1)) || (walkable(dx, -1) && !walkable(0, -1))
+                }
+                (Movement::EightConnected, _) => {
+                    (walkable(1, dy) && !walkable(1, 0)) || (walkable(-1, dy) && !walkable(-1, 0))
+                }
+            };
+            if forced {
+                return Some([col, row]);
+            }
+
+            let open = match (self.movement, self.corner_cutting) {
+                (Movement::FourConnected, _) | (_, CornerCutting::Always) => true,
+                (_, CornerCutting::OneSideOpen) => walkable(dx, 0) || walkable(0, dy),
+                (_, CornerCutting::Never) => walkable(dx, 0) && walkable(0, dy),
+            };
+            if !open {
+                return None;
+            }
+
+            col += dx;
+            row += dy;
+        }
+    }
+
+    /* Links every cell between two jump points of the path to the previous one */
+    fn fill_jumps(&mut self) {
+        let mut index = self.end as usize;
+        while index != self.start as usize {
+            let parent = self.parents[index] as usize;
+            let [mut col, mut row] = self.position(parent);
+            let [to_col, to_row] = self.position(index);
+            let (dx, dy) = ((to_col - col).signum(), (to_row - row).signum());
+
+            let mut previous = parent;
+            while [col, row] != [to_col, to_row] {
+                let step_cost = self.jump_cost([col, row], [col + dx, row + dy]);
+                col += dx;
+                row += dy;
+
+                let next = self.index(col, row);
+                self.parents[next] = previous as i32;
+                self.g_score[next] = self.g_score[previous] + step_cost;
+                previous = next;
+            }
+
+            index = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{create_complex_map, cross_check, path_cost, random_endpoints, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn find_jump_points() {
+        let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder_fill(&mut path_finder);
+        let mut one_way = path_finder;
+        one_way.find(null_mut()).unwrap();
+        let mut jumps = path_finder;
+        assert_eq!(path_finder_find_jump_points(&mut jumps), PATH_FINDER_OK);
+        assert_eq!(jumps.g_score[295], one_way.g_score[295]);
+        assert_eq!(jumps.path().len(), one_way.path().len());
+
+        let closed = |path_finder: &HeapPathFinder| {
+            path_finder
+                .state
+                .iter()
+                .filter(|&&state| state & 0x5 == 0x5)
+                .count()
+        };
+
+        /* Across an open grid only start and end are jump points, the cells between them are
+         * filled in afterwards */
+        let mut open = HeapPathFinder::new(20, 20).unwrap();
+        open.movement = Movement::EightConnected;
+        open.orthogonal_cost = 10;
+        open.diagonal_cost = 14;
+        open.set_end(19, 19).unwrap();
+        assert_eq!(open.find_jump_points(), Ok(true));
+        assert_eq!(closed(&open), 2);
+        assert_eq!(open.path().len(), 20);
+        assert_eq!(path_cost(&open, &[1; 400]), 19 * 14);
+
+        let mut rng = SmallRng::seed_from_u64(15);
+        let costs = vec![1; 40 * 30];
+        for &(movement, corner_cutting) in &[
+            (Movement::FourConnected, CornerCutting::Always),
+            (Movement::EightConnected, CornerCutting::Always),
+            (Movement::EightConnected, CornerCutting::OneSideOpen),
+            (Movement::EightConnected, CornerCutting::Never),
+        ] {
+            let mut expanded = [0, 0];
+            for _map in 0..40 {
+                let mut jumps = random_grid(&mut rng, [40, 30], movement, [1, 4], &[]);
+                jumps.corner_cutting = corner_cutting;
+                let [_, end] = random_endpoints(&mut rng, &mut jumps);
+
+                let found = jumps.find_jump_points().unwrap();
+                if let Some(one_way) = cross_check(&jumps, found, &costs) {
+                    expanded[0] += closed(&one_way);
+                    expanded[1] += closed(&jumps);
+                    assert_eq!(jumps.g_score[end], one_way.g_score[end]);
+                    assert_eq!(path_cost(&jumps, &costs), one_way.g_score[end]);
+                }
+            }
+            assert!(expanded[1] < expanded[0]);
+        }
+    }
+}
@@ -6,75 +6,239 @@ use std::{
     iter,
     os::raw::{c_int, c_void},
     ptr::null_mut,
+    slice,
 };
 
+mod ara_star;
+mod ascii;
+mod bidirectional;
+mod d_star_lite;
+mod error;
+mod heuristic;
+mod hierarchical;
+mod jump_point;
+mod map;
+mod moving_ai;
+mod pnm;
+mod save;
+mod storage;
+mod svg;
+mod theta;
+
+pub use ara_star::AraStar;
+pub use ascii::AsciiMap;
+pub use d_star_lite::DStarLite;
+pub use error::{PathFinderError, PATH_FINDER_OK};
+pub use heuristic::{
+    Chebyshev, Euclidean, Heuristic, HeuristicKind, Manhattan, Octile, StepCosts, Zero,
+};
+pub use hierarchical::Hierarchy;
+pub use map::{FnMap, Map};
+pub use moving_ai::{
+    MovingAiMap, Scenario, ScenarioResult, MOVING_AI_DIAGONAL_COST, MOVING_AI_ORTHOGONAL_COST,
+};
+pub use pnm::{ImageMap, Pnm, SearchColours};
+pub use save::PATH_FINDER_SAVE_VERSION;
+pub use storage::{ArrayStorage, HeapStorage, Storage};
+pub use svg::SvgOptions;
+
+use error::status;
+use map::{Callbacks, Flood, Goals, MapCallbacks, RawCallbacks};
+
 pub const PATH_FINDER_MAX_CELLS: usize = 1024;
 
-#[derive(Copy, Clone)]
+/// The g_score a distance map leaves in the cells it cannot reach.
+pub const PATH_FINDER_UNREACHABLE: i32 = -1;
+
+/// The number of goals above which a multi-goal search stops estimating the distance to the
+/// nearest goal and runs as Dijkstra.
+pub const PATH_FINDER_MAX_GOAL_ESTIMATES: usize = 16;
+
+/* Column and row offsets of the neighbours, orthogonal ones first */
+const NEIGHBOR_OFFSETS: [[i32; 2]; 8] = [
+    [-1, 0],
+    [0, -1],
+    [1, 0],
+    [0, 1],
+    [-1, -1],
+    [1, -1],
+    [1, 1],
+    [-1, 1],
+];
+
+/// Whether a diagonal step may pass between its two orthogonal neighbours when they are blocked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum CornerCutting {
+    /// Diagonal steps ignore the orthogonal neighbours.
+    #[default]
+    Always,
+    /// Diagonal steps need at least one of the orthogonal neighbours to be passable.
+    OneSideOpen,
+    /// Diagonal steps need both orthogonal neighbours to be passable.
+    Never,
+}
+
+/// The cells reachable in a single step from a given cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum Movement {
+    /// Left, top, right and bottom neighbours, with Manhattan distance as heuristic.
+    #[default]
+    FourConnected,
+    /// Orthogonal and diagonal neighbours, with octile distance as heuristic.
+    EightConnected,
+}
+
+pub type PathFinder<const N: usize = PATH_FINDER_MAX_CELLS> = PathFinderBase<ArrayStorage<N>>;
+
+/// A path finder allocating its cells on the heap, for grids of any size.
+pub type HeapPathFinder = PathFinderBase<HeapStorage>;
+
 #[repr(C)]
-pub struct PathFinder {
+pub struct PathFinderBase<S: Storage> {
     pub cols: i32,
     pub rows: i32,
     pub start: i32,
     pub end: i32,
     pub has_path: u8,
-    pub state: [u8; PATH_FINDER_MAX_CELLS],
-    pub parents: [i32; PATH_FINDER_MAX_CELLS],
-    pub g_score: [i32; PATH_FINDER_MAX_CELLS],
-    pub f_score: [i32; PATH_FINDER_MAX_CELLS],
-    pub fill_func: Option<fn(path_finder: &mut PathFinder, col: i32, row: i32) -> u8>,
+    /// The `heuristic_weight` of the search that found the path, 1 for the searches ignoring it.
+    /// A path found by [`find`](Self::find) costs at most this many times the optimal one.
+    pub path_weight: f32,
+    pub state: S::State,
+    pub parents: S::Scores,
+    pub g_score: S::Scores,
+    pub f_score: S::Scores,
+    pub open_set: S::Scores,
+    pub open_set_index: S::Scores,
+    pub open_set_size: i32,
+    pub movement: Movement,
+    pub orthogonal_cost: i32,
+    pub diagonal_cost: i32,
+    pub corner_cutting: CornerCutting,
+    pub heuristic: HeuristicKind,
+    /// Scales the heuristic in the f_score, 1 by default. Weights above 1 trade the optimality
+    /// of the path for fewer expansions, lower or NaN weights search as 1 does.
+    pub heuristic_weight: f32,
     #[allow(clippy::type_complexity)]
-    pub score_func:
-        Option<fn(path_finder: &mut PathFinder, col: i32, row: i32, data: *mut c_void) -> i32>,
+    pub fill_func:
+        Option<extern "C" fn(path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> u8>,
+    #[allow(clippy::type_complexity)]
+    pub score_func: Option<
+        extern "C" fn(
+            path_finder: &mut PathFinderBase<S>,
+            col: i32,
+            row: i32,
+            data: *mut c_void,
+        ) -> i32,
+    >,
+    /// Scales the step into a cell, as [`Map::cost`] does. Costs below 1 count as 1.
+    #[allow(clippy::type_complexity)]
+    pub cost_func: Option<
+        extern "C" fn(
+            path_finder: &mut PathFinderBase<S>,
+            col: i32,
+            row: i32,
+            data: *mut c_void,
+        ) -> i32,
+    >,
+    #[allow(clippy::type_complexity)]
+    pub heuristic_func:
+        Option<extern "C" fn(path_finder: &PathFinderBase<S>, col: i32, row: i32) -> i32>,
     pub data: *mut c_void,
 }
 
-impl PathFinder {
-    pub fn cell(&self, col: i32, row: i32) -> CellRef<'_> {
-        CellRef::new(self, self.cell_index(col, row))
+impl HeapPathFinder {
+    /// Creates an initialized path finder for a `cols` x `rows` grid with every cell passable.
+    ///
+    /// Fails if the grid is empty or if its cell indices would not fit in an `i32`.
+    pub fn new(cols: i32, rows: i32) -> Result<Self, PathFinderError> {
+        if cols < 1 || rows < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+
+        let size = cols
+            .checked_mul(rows)
+            .ok_or(PathFinderError::GridTooLarge)?;
+
+        let size = usize::try_from(size).unwrap();
+        let mut path_finder = Self {
+            state: HeapStorage::state(size),
+            parents: HeapStorage::scores(size),
+            g_score: HeapStorage::scores(size),
+            f_score: HeapStorage::scores(size),
+            open_set: HeapStorage::scores(size),
+            open_set_index: HeapStorage::scores(size),
+            ..Default::default()
+        };
+        path_finder.initialize();
+        path_finder.cols = cols;
+        path_finder.rows = rows;
+
+        Ok(path_finder)
+    }
+}
+
+impl<S: Storage> PathFinderBase<S> {
+    pub fn cell(&self, col: i32, row: i32) -> Result<CellRef<'_>, PathFinderError> {
+        Ok(CellRef::new(self, self.cell_index(col, row)?))
     }
 
-    pub fn get(&self, cell_index: usize) -> CellRef<'_> {
+    pub fn get(&self, cell_index: usize) -> Result<CellRef<'_>, PathFinderError> {
         if cell_index >= self.size() {
-            panic!("cell index out of bounds");
+            return Err(PathFinderError::OutOfBounds);
         }
-        CellRef::new(self, cell_index)
+        Ok(CellRef::new(self, cell_index))
     }
 
-    pub fn get_mut(&mut self, cell_index: usize) -> CellMut<'_> {
+    pub fn get_mut(&mut self, cell_index: usize) -> Result<CellMut<'_>, PathFinderError> {
         if cell_index >= self.size() {
-            panic!("cell index out of bounds");
+            return Err(PathFinderError::OutOfBounds);
         }
-        CellMut::new(self, cell_index)
+        Ok(CellMut::new(self, cell_index))
     }
 
-    pub fn col_and_row_from_index(&self, cell_index: usize) -> [i32; 2] {
+    pub fn col_and_row_from_index(&self, cell_index: usize) -> Result<[i32; 2], PathFinderError> {
         if cell_index >= self.size() {
-            panic!("cell index out of bounds");
+            return Err(PathFinderError::OutOfBounds);
+        }
+
+        Ok(self.position(cell_index))
+    }
+
+    pub fn cell_index(&self, col: i32, row: i32) -> Result<usize, PathFinderError> {
+        if col < 0 || col >= self.cols || row < 0 || row >= self.rows {
+            return Err(PathFinderError::OutOfBounds);
         }
 
-        let cols: usize = self.cols.try_into().unwrap();
-        let col = cell_index % cols;
-        let row = cell_index / cols;
+        Ok(self.index(col, row))
+    }
 
-        [col.try_into().unwrap(), row.try_into().unwrap()]
+    pub fn size(&self) -> usize {
+        usize::try_from(self.cols).unwrap_or(0) * usize::try_from(self.rows).unwrap_or(0)
     }
 
-    pub fn cell_index(&self, col: i32, row: i32) -> usize {
-        if col >= self.cols {
-            panic!("col {} is above the limit of cols ({})", col, self.cols);
+    /// Checks that the grid has cells and that all of them fit in the storage.
+    pub fn check_size(&self) -> Result<(), PathFinderError> {
+        if self.cols < 1 || self.rows < 1 {
+            return Err(PathFinderError::InvalidSize);
         }
 
-        if row >= self.rows {
-            panic!("row {} is above the limit of rows ({})", row, self.rows);
+        match self.cols.checked_mul(self.rows) {
+            Some(size) if size as usize <= self.state.as_ref().len() => Ok(()),
+            _ => Err(PathFinderError::GridTooLarge),
         }
+    }
 
-        usize::try_from(row).unwrap() * usize::try_from(self.cols).unwrap()
-            + usize::try_from(col).unwrap()
+    /* Unchecked conversions for cells already known to be on the grid */
+
+    pub(crate) fn index(&self, col: i32, row: i32) -> usize {
+        (row * self.cols + col) as usize
     }
 
-    pub fn size(&self) -> usize {
-        usize::try_from(self.cols).unwrap() * usize::try_from(self.cols).unwrap()
+    pub(crate) fn position(&self, cell_index: usize) -> [i32; 2] {
+        let cell_index = cell_index as i32;
+        [cell_index % self.cols, cell_index / self.cols]
     }
 }
 
@@ -87,8 +251,8 @@ pub struct CellRef<'a> {
 }
 
 impl<'a> CellRef<'a> {
-    pub fn new(path_finder: &'a PathFinder, index: usize) -> Self {
-        let PathFinder {
+    pub fn new<S: Storage>(path_finder: &'a PathFinderBase<S>, index: usize) -> Self {
+        let PathFinderBase {
             state,
             parents,
             g_score,
@@ -133,8 +297,8 @@ pub struct CellMut<'a> {
 }
 
 impl<'a> CellMut<'a> {
-    pub fn new(path_finder: &'a mut PathFinder, index: usize) -> Self {
-        let PathFinder {
+    pub fn new<S: Storage>(path_finder: &'a mut PathFinderBase<S>, index: usize) -> Self {
+        let PathFinderBase {
             state,
             parents,
             g_score,
@@ -196,7 +360,45 @@ impl<'a> PartialEq<Cell> for CellMut<'a> {
     }
 }
 
-impl Debug for PathFinder {
+impl<S: Storage> Clone for PathFinderBase<S> {
+    fn clone(&self) -> Self {
+        Self {
+            cols: self.cols,
+            rows: self.rows,
+            start: self.start,
+            end: self.end,
+            has_path: self.has_path,
+            path_weight: self.path_weight,
+            state: self.state.clone(),
+            parents: self.parents.clone(),
+            g_score: self.g_score.clone(),
+            f_score: self.f_score.clone(),
+            open_set: self.open_set.clone(),
+            open_set_index: self.open_set_index.clone(),
+            open_set_size: self.open_set_size,
+            movement: self.movement,
+            orthogonal_cost: self.orthogonal_cost,
+            diagonal_cost: self.diagonal_cost,
+            corner_cutting: self.corner_cutting,
+            heuristic: self.heuristic,
+            heuristic_weight: self.heuristic_weight,
+            fill_func: self.fill_func,
+            score_func: self.score_func,
+            cost_func: self.cost_func,
+            heuristic_func: self.heuristic_func,
+            data: self.data,
+        }
+    }
+}
+
+impl<S: Storage> Copy for PathFinderBase<S>
+where
+    S::State: Copy,
+    S::Scores: Copy,
+{
+}
+
+impl<S: Storage> Debug for PathFinderBase<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PathFinder")
             .field("cols", &self.cols)
@@ -204,15 +406,16 @@ impl Debug for PathFinder {
             .field("start", &self.start)
             .field("end", &self.end)
             .field("has_path", &self.has_path)
-            .field("state", &(&self.state as &[_]))
-            .field("parents", &(&self.parents as &[_]))
-            .field("g_score", &(&self.g_score as &[_]))
-            .field("f_score", &(&self.f_score as &[_]))
+            .field("path_weight", &self.path_weight)
+            .field("state", &self.state.as_ref())
+            .field("parents", &self.parents.as_ref())
+            .field("g_score", &self.g_score.as_ref())
+            .field("f_score", &self.f_score.as_ref())
             .finish()
     }
 }
 
-impl Default for PathFinder {
+impl<S: Storage> Default for PathFinderBase<S> {
     fn default() -> Self {
         Self {
             cols: Default::default(),
@@ -220,19 +423,31 @@ impl Default for PathFinder {
             start: Default::default(),
             end: Default::default(),
             has_path: Default::default(),
-            state: [0; PATH_FINDER_MAX_CELLS],
-            parents: [0; PATH_FINDER_MAX_CELLS],
-            g_score: [0; PATH_FINDER_MAX_CELLS],
-            f_score: [0; PATH_FINDER_MAX_CELLS],
+            path_weight: 1.0,
+            state: S::state(0),
+            parents: S::scores(0),
+            g_score: S::scores(0),
+            f_score: S::scores(0),
+            open_set: S::scores(0),
+            open_set_index: S::scores(0),
+            open_set_size: Default::default(),
+            movement: Default::default(),
+            orthogonal_cost: 1,
+            diagonal_cost: 1,
+            corner_cutting: Default::default(),
+            heuristic: Default::default(),
+            heuristic_weight: 1.0,
             fill_func: Default::default(),
             score_func: Default::default(),
+            cost_func: Default::default(),
+            heuristic_func: Default::default(),
             data: null_mut(),
         }
     }
 }
 
-impl PartialEq for PathFinder {
-    fn eq(&self, other: &PathFinder) -> bool {
+impl<S: Storage> PartialEq for PathFinderBase<S> {
+    fn eq(&self, other: &PathFinderBase<S>) -> bool {
         self.cols.eq(&other.cols)
             && self.rows.eq(&other.rows)
             && self.start.eq(&other.start)
@@ -240,75 +455,181 @@ impl PartialEq for PathFinder {
             && self.has_path.eq(&other.has_path)
             && self
                 .state
+                .as_ref()
                 .iter()
-                .zip(other.state.iter())
+                .zip(other.state.as_ref())
                 .all(|(a, b)| a == b)
             && self
                 .parents
+                .as_ref()
                 .iter()
-                .zip(other.parents.iter())
+                .zip(other.parents.as_ref())
                 .all(|(a, b)| a == b)
             && self
                 .g_score
+                .as_ref()
                 .iter()
-                .zip(other.g_score.iter())
+                .zip(other.g_score.as_ref())
                 .all(|(a, b)| a == b)
             && self
                 .f_score
+                .as_ref()
                 .iter()
-                .zip(other.f_score.iter())
+                .zip(other.f_score.as_ref())
                 .all(|(a, b)| a == b)
     }
 }
 
-extern "C" fn path_finder_heuristic(path_finder: &PathFinder, cell: i32) -> i32 {
-    let cell_y = cell / path_finder.cols;
-    let cell_x = cell - cell_y * path_finder.cols;
-    let end_y = path_finder.end / path_finder.cols;
-    let end_x = path_finder.end - end_y * path_finder.cols;
-    let dx = if cell_x > end_x {
-        cell_x - end_x
-    } else {
-        end_x - cell_x
-    };
-    let dy = if cell_y > end_y {
-        cell_y - end_y
+pub(crate) extern "C" fn path_finder_heuristic<S: Storage>(
+    path_finder: &PathFinderBase<S>,
+    cell: i32,
+) -> i32 {
+    let [col, row] = path_finder.position(cell.try_into().unwrap());
+    match path_finder.heuristic_func {
+        Some(heuristic_func) => heuristic_func(path_finder, col, row),
+        None => path_finder.estimate(path_finder.builtin_heuristic(), cell),
+    }
+}
+
+/*
+ * The open set is a binary min-heap of cell indices ordered by f_score, with ties going to the
+ * lowest cell index. `open_set_index` maps every open cell to its position in the heap, so that a
+ * cell whose score improves can be moved up in place instead of being pushed twice.
+ */
+
+extern "C" fn path_finder_open_set_is_empty<S: Storage>(path_finder: &PathFinderBase<S>) -> u8 {
+    (path_finder.open_set_size == 0).into()
+}
+
+extern "C" fn path_finder_lowest_in_open_set<S: Storage>(path_finder: &PathFinderBase<S>) -> i32 {
+    if path_finder.open_set_size == 0 {
+        0
     } else {
-        end_y - cell_y
-    };
-    dx + dy
+        path_finder.open_set[0]
+    }
+}
+
+extern "C" fn path_finder_open_set_less<S: Storage>(
+    path_finder: &PathFinderBase<S>,
+    a: i32,
+    b: i32,
+) -> u8 {
+    let a = (path_finder.f_score[a as usize], a);
+    let b = (path_finder.f_score[b as usize], b);
+    (a < b).into()
 }
 
-extern "C" fn path_finder_open_set_is_empty(path_finder: &PathFinder) -> u8 {
-    use std::ops::Not;
+extern "C" fn path_finder_open_set_swap<S: Storage>(
+    path_finder: &mut PathFinderBase<S>,
+    i: i32,
+    j: i32,
+) {
+    let a = path_finder.open_set[i as usize];
+    let b = path_finder.open_set[j as usize];
+    path_finder.open_set[i as usize] = b;
+    path_finder.open_set[j as usize] = a;
+    path_finder.open_set_index[b as usize] = i;
+    path_finder.open_set_index[a as usize] = j;
+}
 
-    path_finder
-        .state
-        .iter()
-        .take((path_finder.cols * path_finder.rows) as usize)
-        .any(|state| state & 0x2 == 0x2)
-        .not()
+extern "C" fn path_finder_open_set_sift_up<S: Storage>(
+    path_finder: &mut PathFinderBase<S>,
+    mut position: i32,
+) {
+    while position > 0 {
+        let parent = (position - 1) / 2;
+        let cell = path_finder.open_set[position as usize];
+        let parent_cell = path_finder.open_set[parent as usize];
+        if path_finder_open_set_less(path_finder, cell, parent_cell) == 0 {
+            break;
+        }
+
+        path_finder_open_set_swap(path_finder, position, parent);
+        position = parent;
+    }
+}
+
+extern "C" fn path_finder_open_set_sift_down<S: Storage>(
+    path_finder: &mut PathFinderBase<S>,
+    mut position: i32,
+) {
+    loop {
+        let left = position * 2 + 1;
+        if left >= path_finder.open_set_size {
+            break;
+        }
+
+        let right = left + 1;
+        let left_cell = path_finder.open_set[left as usize];
+        let child = if right < path_finder.open_set_size
+            && path_finder_open_set_less(
+                path_finder,
+                path_finder.open_set[right as usize],
+                left_cell,
+            ) == 1
+        {
+            right
+        } else {
+            left
+        };
+
+        let cell = path_finder.open_set[position as usize];
+        let child_cell = path_finder.open_set[child as usize];
+        if path_finder_open_set_less(path_finder, child_cell, cell) == 0 {
+            break;
+        }
+
+        path_finder_open_set_swap(path_finder, position, child);
+        position = child;
+    }
+}
+
+extern "C" fn path_finder_open_set_contains<S: Storage>(
+    path_finder: &PathFinderBase<S>,
+    cell: i32,
+) -> u8 {
+    let position = path_finder.open_set_index[cell as usize];
+    (path_finder.state[cell as usize] & 0x2 == 0x2
+        && position >= 0
+        && position < path_finder.open_set_size
+        && path_finder.open_set[position as usize] == cell)
         .into()
 }
 
-extern "C" fn path_finder_lowest_in_open_set(path_finder: &PathFinder) -> i32 {
-    path_finder
-        .state
-        .iter()
-        .zip(path_finder.f_score.iter())
-        .enumerate()
-        .take((path_finder.cols * path_finder.rows) as usize)
-        .filter(|(_, (&state, _))| state & 0x2 == 0x2)
-        .map(|(index, (_, f_score))| (index, f_score))
-        .min_by_key(|(_, &f_score)| f_score)
-        .map(|(index, _)| index)
-        .unwrap_or(0)
-        .try_into()
-        .unwrap()
-}
-
-extern "C" fn path_finder_reconstruct_path(path_finder: &mut PathFinder) {
-    let &mut PathFinder {
+/* Opens `cell`, or restores the heap order after its f_score changed if it is already open */
+extern "C" fn path_finder_open_set_push<S: Storage>(
+    path_finder: &mut PathFinderBase<S>,
+    cell: i32,
+) {
+    if path_finder_open_set_contains(path_finder, cell) == 1 {
+        let position = path_finder.open_set_index[cell as usize];
+        path_finder_open_set_sift_up(path_finder, position);
+        let position = path_finder.open_set_index[cell as usize];
+        path_finder_open_set_sift_down(path_finder, position);
+    } else {
+        let position = path_finder.open_set_size;
+        path_finder.open_set[position as usize] = cell;
+        path_finder.open_set_index[cell as usize] = position;
+        path_finder.open_set_size += 1;
+        path_finder.state[cell as usize] |= 0x2;
+        path_finder_open_set_sift_up(path_finder, position);
+    }
+}
+
+extern "C" fn path_finder_open_set_pop<S: Storage>(path_finder: &mut PathFinderBase<S>) -> i32 {
+    let lowest = path_finder.open_set[0];
+    path_finder.open_set_size -= 1;
+    if path_finder.open_set_size > 0 {
+        path_finder_open_set_swap(path_finder, 0, path_finder.open_set_size);
+        path_finder_open_set_sift_down(path_finder, 0);
+    }
+    path_finder.state[lowest as usize] &= !0x2;
+
+    lowest
+}
+
+extern "C" fn path_finder_reconstruct_path<S: Storage>(path_finder: &mut PathFinderBase<S>) {
+    let &mut PathFinderBase {
         start,
         end,
         ref parents,
@@ -316,463 +637,2234 @@ extern "C" fn path_finder_reconstruct_path(path_finder: &mut PathFinder) {
         ..
     } = path_finder;
 
+    /* Sources are their own parents, see `add_source` */
     iter::successors(Some(end), |&index| Some(parents[index as usize]))
-        .take_while(|&index| index != start)
+        .take_while(|&index| index != start && parents[index as usize] != index)
         .skip(1)
         .for_each(|index| state[index as usize] |= 0x8);
 }
 
-#[no_mangle]
-pub extern "C" fn path_finder_fill(path_finder: &mut PathFinder) {
-    let fill_func = path_finder.fill_func.expect("non-null function pointer");
-    let size: usize = (path_finder.rows * path_finder.cols).try_into().unwrap();
+/* Cells the distance map never closed are out of reach of the start */
+extern "C" fn path_finder_mark_unreachable<S: Storage>(path_finder: &mut PathFinderBase<S>) {
+    let size = path_finder.size();
+    let PathFinderBase {
+        ref state,
+        ref mut g_score,
+        ..
+    } = path_finder;
 
-    let mut index_iter = 0..size.min(path_finder.state.len());
-    for row in 0..path_finder.rows {
-        for col in 0..path_finder.cols {
-            let index = index_iter.next().unwrap();
+    state
+        .as_ref()
+        .iter()
+        .zip(g_score.as_mut())
+        .take(size)
+        .filter(|&(&state, _)| state & 0x5 != 0x5)
+        .for_each(|(_, g_score)| *g_score = PATH_FINDER_UNREACHABLE);
+}
 
-            if fill_func(path_finder, col, row) == 0 {
-                path_finder.state[index] &= !0x1;
-            } else {
-                path_finder.state[index] |= 0x1;
-            }
+impl<S: Storage> PathFinderBase<S> {
+    pub(crate) fn neighbor_offsets(&self) -> &'static [[i32; 2]] {
+        match self.movement {
+            Movement::FourConnected => &NEIGHBOR_OFFSETS[..4],
+            Movement::EightConnected => &NEIGHBOR_OFFSETS[..],
         }
     }
-}
 
-#[no_mangle]
-pub extern "C" fn path_finder_begin(path_finder: &mut PathFinder) {
-    path_finder.state[path_finder.start as usize] |= 0x2;
-}
+    /* The index of the cell at `offset` from (col, row) and the cost of stepping there, if the
+     * step stays on the grid and does not cut a corner the policy forbids */
+    pub(crate) fn neighbor(&self, col: i32, row: i32, offset: [i32; 2]) -> Option<(usize, i32)> {
+        let [x, y] = offset;
+        let (neighbor_col, neighbor_row) = (col + x, row + y);
+        if neighbor_col < 0
+            || neighbor_col >= self.cols
+            || neighbor_row < 0
+            || neighbor_row >= self.rows
+        {
+            return None;
+        }
 
-#[no_mangle]
-pub extern "C" fn path_finder_find_step(path_finder: &mut PathFinder, data: *mut c_void) -> u8 {
-    let count = path_finder.size();
-    let current = path_finder_lowest_in_open_set(path_finder);
+        let step_cost = if x == 0 || y == 0 {
+            self.orthogonal_cost
+        } else {
+            let horizontal = self.state[self.index(neighbor_col, row)] & 0x1 == 0x1;
+            let vertical = self.state[self.index(col, neighbor_row)] & 0x1 == 0x1;
+            let allowed = match self.corner_cutting {
+                CornerCutting::Always => true,
+                CornerCutting::OneSideOpen => horizontal || vertical,
+                CornerCutting::Never => horizontal && vertical,
+            };
+            if !allowed {
+                return None;
+            }
 
-    if current == path_finder.end {
-        path_finder_reconstruct_path(path_finder);
-        path_finder.has_path = 1;
-        0
-    } else if path_finder_open_set_is_empty(path_finder) == 1 {
-        path_finder.has_path = 0;
-        0
-    } else {
-        let current_index: usize = current.try_into().unwrap();
+            self.diagonal_cost
+        };
 
-        path_finder.state[current_index] = (path_finder.state[current_index] & !0x2) | 0x4;
+        Some((self.index(neighbor_col, neighbor_row), step_cost))
+    }
 
-        let neighbors = {
-            let left = if current % path_finder.cols == 0 {
-                -1
-            } else {
-                current - 1
-            };
-            let top = current - path_finder.cols;
-            let right = if (current + 1) % path_finder.cols == 0 {
-                -1
-            } else {
-                current + 1
-            };
-            let bottom = current + path_finder.cols;
+    pub fn step_costs(&self) -> StepCosts {
+        StepCosts {
+            orthogonal: self.orthogonal_cost,
+            diagonal: self.diagonal_cost,
+        }
+    }
 
-            [left, top, right, bottom]
-        };
+    pub(crate) fn builtin_heuristic(&self) -> &'static dyn Heuristic {
+        match (self.heuristic, self.movement) {
+            (HeuristicKind::Auto, Movement::FourConnected) | (HeuristicKind::Manhattan, _) => {
+                &Manhattan
+            }
+            (HeuristicKind::Auto, Movement::EightConnected) | (HeuristicKind::Octile, _) => &Octile,
+            (HeuristicKind::Chebyshev, _) => &Chebyshev,
+            (HeuristicKind::Euclidean, _) => &Euclidean,
+            (HeuristicKind::Zero, _) => &Zero,
+        }
+    }
 
-        let cols: usize = path_finder.cols.try_into().unwrap();
-        let g_score = path_finder.g_score[current_index] + 1;
-        let score_func = path_finder.score_func;
+    /* The estimate of `heuristic` from the cell with index `cell` to the end */
+    pub(crate) fn estimate<H: Heuristic + ?Sized>(&self, heuristic: &H, cell: i32) -> i32 {
+        let cell = self.position(cell.try_into().unwrap());
+        let end = self.position(self.end.try_into().unwrap());
+        heuristic.estimate(cell, end, self.step_costs())
+    }
 
-        neighbors
-            .iter()
-            .filter_map(|&n| usize::try_from(n).ok())
-            .filter(|&n| n < count)
-            .for_each(|n| {
-                let cell = path_finder.get_mut(n);
-                if *cell.state & 0x4 == 0 {
-                    if *cell.state & 0x1 == 0 {
-                        *cell.state |= 0x4;
-                    } else if *cell.state & 0x2 == 0 || g_score < *cell.g_score {
-                        *cell.parent = current;
-                        *cell.g_score = g_score;
-                        let heuristics = path_finder_heuristic(path_finder, n.try_into().unwrap());
-
-                        let cell = path_finder.get_mut(n);
-                        *cell.f_score = g_score + heuristics;
-
-                        let cell = match score_func {
-                            Some(score_func) => {
-                                let score = score_func(
-                                    path_finder,
-                                    (n % cols).try_into().unwrap(),
-                                    (n / cols).try_into().unwrap(),
-                                    data,
-                                );
-
-                                let cell = path_finder.get_mut(n);
-                                *cell.f_score += score;
-
-                                cell
-                            }
-                            None => cell,
-                        };
+    pub fn fill(&mut self) -> Result<(), PathFinderError> {
+        let fill_func = self.fill_func.ok_or(PathFinderError::MissingFillFunc)?;
+        self.fill_by(|path_finder, col, row| fill_func(path_finder, col, row) != 0)
+    }
 
-                        *cell.state |= 0x2;
-                    }
+    /// Sets the passability of every cell from `map`.
+    pub fn fill_from<M: Map + ?Sized>(&mut self, map: &mut M) -> Result<(), PathFinderError> {
+        self.fill_by(|_path_finder, col, row| map.is_passable(col, row))
+    }
+
+    /// Sets the passability of every cell from a `(col, row)` closure.
+    pub fn fill_with<F: FnMut(i32, i32) -> bool>(
+        &mut self,
+        mut passable: F,
+    ) -> Result<(), PathFinderError> {
+        self.fill_from(&mut passable)
+    }
+
+    fn fill_by<F: FnMut(&mut Self, i32, i32) -> bool>(
+        &mut self,
+        mut passable: F,
+    ) -> Result<(), PathFinderError> {
+        self.check_size()?;
+
+        let mut index_iter = 0..self.size();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let index = index_iter.next().unwrap();
+
+                if passable(self, col, row) {
+                    self.state[index] |= 0x1;
+                } else {
+                    self.state[index] &= !0x1;
                 }
-            });
+            }
+        }
 
-        1
+        Ok(())
     }
-}
-#[no_mangle]
-pub extern "C" fn path_finder_find(path_finder: &mut PathFinder, data: *mut c_void) {
-    path_finder_begin(path_finder);
-    while path_finder_find_step(path_finder, data) as c_int == 1 as c_int {}
-}
 
-#[no_mangle]
-pub extern "C" fn path_finder_get_heuristic_score(
-    path_finder: &mut PathFinder,
-    col: i32,
-    row: i32,
-) -> i32 {
-    path_finder.f_score[(row * path_finder.cols + col) as usize]
-}
+    /// Checks that the grid is valid and that both start and end are passable cells on it.
+    pub fn check_endpoints(&self) -> Result<(), PathFinderError> {
+        self.check_size()?;
+        self.check_endpoint(self.start, PathFinderError::StartImpassable)?;
+        self.check_endpoint(self.end, PathFinderError::EndImpassable)
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_passable(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (path_finder.state[(row * path_finder.cols + col) as usize] as c_int & 0x1 as c_int
-        == 0x1 as c_int) as c_int as u8
-}
+    fn check_endpoint(
+        &self,
+        index: i32,
+        impassable: PathFinderError,
+    ) -> Result<(), PathFinderError> {
+        let index = usize::try_from(index)
+            .ok()
+            .filter(|&index| index < self.size())
+            .ok_or(PathFinderError::OutOfBounds)?;
+
+        if self.state[index] & 0x1 == 0 {
+            Err(impassable)
+        } else {
+            Ok(())
+        }
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_closed(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (path_finder.state[(row * path_finder.cols + col) as usize] as c_int & 0x4 as c_int
-        == 0x4 as c_int) as c_int as u8
-}
+    pub fn begin(&mut self) {
+        path_finder_open_set_push(self, self.start);
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_open(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (path_finder.state[(row * path_finder.cols + col) as usize] as c_int & 0x2 as c_int
-        == 0x2 as c_int) as c_int as u8
-}
+    pub fn find_step(&mut self, data: *mut c_void) -> u8 {
+        self.expand(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_path(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (path_finder.state[(row * path_finder.cols + col) as usize] as c_int & 0x8 as c_int
-        == 0x8 as c_int) as c_int as u8
-}
+    /// Like [`find_step`](Self::find_step), estimating the remaining cost with `heuristic`
+    /// instead of `heuristic_func` or the built-in `heuristic`.
+    pub fn find_step_with<H: Heuristic + ?Sized>(
+        &mut self,
+        data: *mut c_void,
+        heuristic: &H,
+    ) -> u8 {
+        self.expand(&mut RawCallbacks {
+            data,
+            heuristic: Some(heuristic),
+        })
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_start(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (row * path_finder.cols + col == path_finder.start) as c_int as u8
-}
+    /// Like [`find_step`](Self::find_step), taking the traversal costs and scores from `map`
+    /// instead of `cost_func` and `score_func`. Returns whether the search is still running.
+    pub fn find_step_in<M: Map + ?Sized>(&mut self, map: &mut M) -> bool {
+        self.expand(&mut MapCallbacks(map)) == 1
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_is_end(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-    (row * path_finder.cols + col == path_finder.end) as c_int as u8
-}
+    fn expand<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> u8 {
+        /* The lowest cell of an empty open set reads as cell 0, which may be the goal */
+        if path_finder_open_set_is_empty(self) == 1 {
+            self.has_path = 0;
+            return 0;
+        }
 
-#[no_mangle]
-pub extern "C" fn path_finder_set_start(path_finder: &mut PathFinder, col: i32, row: i32) {
-    path_finder.start = row * path_finder.cols + col;
-}
+        let current = path_finder_lowest_in_open_set(self);
+        if callbacks.is_goal(self, current) {
+            self.end = current;
+            path_finder_reconstruct_path(self);
+            self.has_path = 1;
+            self.path_weight = self.weight();
+            0
+        } else {
+            self.expand_lowest(callbacks);
+            1
+        }
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_set_end(path_finder: &mut PathFinder, col: i32, row: i32) {
-    path_finder.end = row * path_finder.cols + col;
-}
+    /// Like [`find_step`](Self::find_step), ignoring the end and the heuristic to compute the
+    /// distance from start to every reachable cell. Once the open set is empty, marks the g_score
+    /// of every other cell with [`PATH_FINDER_UNREACHABLE`].
+    pub fn distance_step(&mut self, data: *mut c_void) -> u8 {
+        self.flood(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
 
-#[no_mangle]
-pub extern "C" fn path_finder_clear_path(path_finder: &mut PathFinder) {
-    let mut i: i32 = 0;
-    i = 0 as c_int;
-    while i < PATH_FINDER_MAX_CELLS as c_int {
-        path_finder.state[i as usize] = (path_finder.state[i as usize] as c_int
-            & !(0x2 as c_int | 0x4 as c_int | 0x8 as c_int))
-            as u8;
-        path_finder.parents[i as usize] = 0 as c_int;
-        path_finder.g_score[i as usize] = 0 as c_int;
-        path_finder.f_score[i as usize] = 0 as c_int;
-        i += 1
+    /// Like [`distance_step`](Self::distance_step), taking the traversal costs from `map`.
+    pub fn distance_step_in<M: Map + ?Sized>(&mut self, map: &mut M) -> bool {
+        self.flood(&mut MapCallbacks(map)) == 1
     }
-    path_finder.has_path = 0 as c_int as u8;
-}
 
-#[no_mangle]
-pub extern "C" fn path_finder_initialize(path_finder: &mut PathFinder) {
-    let mut i: i32 = 0;
-    i = 0 as c_int;
-    while i < PATH_FINDER_MAX_CELLS as c_int {
-        path_finder.parents[i as usize] = 0 as c_int;
-        path_finder.g_score[i as usize] = 0 as c_int;
-        path_finder.f_score[i as usize] = 0 as c_int;
-        path_finder.state[i as usize] = 0x1 as c_int as u8;
-        i += 1
-    }
-    path_finder.rows = 0 as c_int;
-    path_finder.cols = 0 as c_int;
-    path_finder.start = 0 as c_int;
-    path_finder.end = 0 as c_int;
-    path_finder.has_path = 0 as c_int as u8;
-}
+    fn flood<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> u8 {
+        if path_finder_open_set_is_empty(self) == 1 {
+            path_finder_mark_unreachable(self);
+            0
+        } else {
+            self.expand_lowest(&mut Flood(callbacks));
+            1
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /* The heuristic weight searched with. Weights below 1 cannot tighten the bound, and negative
+     * or NaN ones would break it */
+    fn weight(&self) -> f32 {
+        self.heuristic_weight.max(1.0)
+    }
 
-    #[test]
-    fn open_set_is_empty() {
-        let mut path_finder = PathFinder {
-            cols: 5,
-            rows: 4,
-            ..Default::default()
-        };
+    fn expand_lowest<C: Callbacks<S>>(&mut self, callbacks: &mut C) {
+        let current = path_finder_open_set_pop(self);
+        let current_index: usize = current.try_into().unwrap();
 
-        assert_eq!(path_finder_open_set_is_empty(&path_finder), 1);
+        self.state[current_index] |= 0x4;
 
-        path_finder.state[7] = 2;
-        assert_eq!(path_finder_open_set_is_empty(&path_finder), 0);
+        let [col, row] = self.position(current_index);
+        let current_g_score = self.g_score[current_index];
+
+        for &offset in self.neighbor_offsets() {
+            let (n, step_cost) = match self.neighbor(col, row, offset) {
+                Some(neighbor) => neighbor,
+                None => continue,
+            };
+
+            let state = self.state[n];
+            if state & 0x4 != 0 {
+                continue;
+            }
+            if state & 0x1 == 0 {
+                self.state[n] |= 0x4;
+                continue;
+            }
+
+            /* The cost of the cell scales the step into it, unlike the score which only
+             * biases the f_score */
+            let [neighbor_col, neighbor_row] = self.position(n);
+            let g_score =
+                current_g_score + step_cost * callbacks.step_cost(self, neighbor_col, neighbor_row);
+
+            let cell = CellMut::new(self, n);
+            if *cell.state & 0x2 == 0 || g_score < *cell.g_score {
+                *cell.parent = current;
+                *cell.g_score = g_score;
+                let heuristics = callbacks.heuristic(self, n.try_into().unwrap());
+                let heuristics = (f64::from(self.weight()) * f64::from(heuristics)).round() as i32;
+                let score = callbacks.score(self, neighbor_col, neighbor_row);
+
+                self.f_score[n] = g_score + heuristics + score;
+                path_finder_open_set_push(self, n.try_into().unwrap());
+            }
+        }
     }
 
-    #[test]
-    fn lowest_in_open_set() {
-        let path_finder = PathFinder::default();
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 0);
+    /// Searches from start to end. Returns whether a path was found.
+    pub fn find(&mut self, data: *mut c_void) -> Result<bool, PathFinderError> {
+        self.check_endpoints()?;
+        self.begin();
+        while self.find_step(data) as c_int == 1 as c_int {}
 
-        let mut path_finder = PathFinder {
-            cols: 5,
-            rows: 4,
-            ..Default::default()
-        };
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 0);
+        Ok(self.has_path == 1)
+    }
 
-        path_finder.state[3] = 0x2;
-        path_finder.f_score[3] = -5;
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 3);
+    pub fn find_with<H: Heuristic + ?Sized>(
+        &mut self,
+        data: *mut c_void,
+        heuristic: &H,
+    ) -> Result<bool, PathFinderError> {
+        self.check_endpoints()?;
+        self.begin();
+        while self.find_step_with(data, heuristic) == 1 {}
 
-        path_finder.state[6] = 0x2;
-        path_finder.f_score[6] = -9;
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 6);
+        Ok(self.has_path == 1)
+    }
 
-        path_finder.state[11] = 0x2;
-        path_finder.f_score[11] = -7;
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 6);
+    /// Fills the grid from `map` and searches it from start to end. Returns whether a path was
+    /// found.
+    pub fn find_in<M: Map + ?Sized>(&mut self, map: &mut M) -> Result<bool, PathFinderError> {
+        self.fill_from(map)?;
+        self.check_endpoints()?;
+        self.begin();
+        while self.find_step_in(map) {}
 
-        path_finder.f_score[11] = -10;
-        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 11);
+        Ok(self.has_path == 1)
     }
 
-    #[test]
-    fn reconstruct_path() {
-        /*
-         * Representation
-         *
-         * /-----\
-         * | >>>v|
-         * | S v<|
-         * |v<<< |
-         * |>>>E |
-         * \-----/
-         */
+    /// Searches from start to the nearest of the goal cells. Sets `end` to the goal reached and
+    /// returns it, or `None` if no goal can be reached.
+    pub fn find_nearest(&mut self, data: *mut c_void) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.find_nearest_by(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
 
-        let mut path_finder = PathFinder {
-            cols: 5,
-            rows: 4,
-            start: 6,
-            end: 18,
-            ..Default::default()
+    /// Fills the grid from `map` and searches from start to the nearest of the goal cells.
+    pub fn find_nearest_in<M: Map + ?Sized>(
+        &mut self,
+        map: &mut M,
+    ) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.fill_from(map)?;
+        self.find_nearest_by(&mut MapCallbacks(map))
+    }
+
+    fn find_nearest_by<C: Callbacks<S>>(
+        &mut self,
+        callbacks: &mut C,
+    ) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.check_size()?;
+        self.check_endpoint(self.start, PathFinderError::StartImpassable)?;
+
+        let mut goals: Vec<_> = (0..self.size())
+            .filter(|&index| self.state[index] & 0x11 == 0x11)
+            .map(|index| self.position(index))
+            .collect();
+        self.has_path = 0;
+        if goals.is_empty() {
+            return Ok(None);
+        }
+        if goals.len() > PATH_FINDER_MAX_GOAL_ESTIMATES {
+            goals.clear();
+        }
+
+        let mut callbacks = Goals { callbacks, goals };
+        self.begin();
+        while self.expand(&mut callbacks) == 1 {}
+
+        Ok(Some(self.position(self.end as usize)).filter(|_| self.has_path == 1))
+    }
+
+    /// Searches from the nearest of the sources to end. Sets `start` to the source the path
+    /// comes from and returns it, or `None` if end cannot be reached.
+    pub fn find_from_sources(
+        &mut self,
+        data: *mut c_void,
+    ) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.find_from_sources_by(&mut RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
+        })
+    }
+
+    /// Fills the grid from `map` and searches from the nearest of the sources to end.
+    pub fn find_from_sources_in<M: Map + ?Sized>(
+        &mut self,
+        map: &mut M,
+    ) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.fill_from(map)?;
+        self.find_from_sources_by(&mut MapCallbacks(map))
+    }
+
+    fn find_from_sources_by<C: Callbacks<S>>(
+        &mut self,
+        callbacks: &mut C,
+    ) -> Result<Option<[i32; 2]>, PathFinderError> {
+        self.check_size()?;
+        self.check_endpoint(self.end, PathFinderError::EndImpassable)?;
+        self.begin_sources(callbacks)?;
+
+        /* No cell is the start until the path tells which source it comes from */
+        let start = self.start;
+        self.start = -1;
+        while self.expand(callbacks) == 1 {}
+
+        let [col, row] = self.position(self.end as usize);
+        match self.source_of(col, row).filter(|_| self.has_path == 1) {
+            Some(source) => {
+                self.start = self.index(source[0], source[1]) as i32;
+                Ok(Some(source))
+            }
+            None => {
+                self.start = start;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Computes the distance from the nearest of the sources to every cell, see
+    /// [`distance_step`](Self::distance_step). [`source_of`](Self::source_of) then tells which
+    /// source every cell belongs to.
+    pub fn distance_map_from_sources(&mut self, data: *mut c_void) -> Result<(), PathFinderError> {
+        self.check_size()?;
+        let mut callbacks = RawCallbacks::<dyn Heuristic> {
+            data,
+            heuristic: None,
         };
+        self.begin_sources(&mut Flood(&mut callbacks))?;
+        while self.distance_step(data) == 1 {}
 
-        let parents = &mut path_finder.parents;
+        Ok(())
+    }
+
+    /* Opens every passable source with its initial g_score */
+    fn begin_sources<C: Callbacks<S>>(&mut self, callbacks: &mut C) -> Result<(), PathFinderError> {
+        let mut sources = 0;
+        for index in 0..self.size() {
+            if self.state[index] & 0x21 == 0x21 {
+                let cell = index as i32;
+                self.f_score[index] = self.g_score[index] + callbacks.heuristic(self, cell);
+                path_finder_open_set_push(self, cell);
+                sources += 1;
+            }
+        }
+
+        if sources == 0 {
+            Err(PathFinderError::StartImpassable)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The source whose search reached (col, row), or `None` if no search from sources did.
+    pub fn source_of(&self, col: i32, row: i32) -> Option<[i32; 2]> {
+        let index = self.cell_index(col, row).ok()?;
+        if self.state[index] & 0x1 == 0 || self.state[index] & 0x6 == 0 {
+            return None;
+        }
+
+        iter::successors(Some(index), |&index| {
+            Some(self.parents[index] as usize).filter(|&parent| parent != index)
+        })
+        .take(self.size())
+        .last()
+        .filter(|&root| self.state[root] & 0x20 != 0 && self.parents[root] as usize == root)
+        .map(|root| self.position(root))
+    }
+
+    /// Computes the distance from start to every cell into `g_score`, see
+    /// [`distance_step`](Self::distance_step).
+    pub fn distance_map(&mut self, data: *mut c_void) -> Result<(), PathFinderError> {
+        self.check_size()?;
+        self.check_endpoint(self.start, PathFinderError::StartImpassable)?;
+        self.begin();
+        while self.distance_step(data) == 1 {}
+
+        Ok(())
+    }
+
+    /// Fills the grid from `map` and computes the distance from start to every cell.
+    pub fn distance_map_in<M: Map + ?Sized>(&mut self, map: &mut M) -> Result<(), PathFinderError> {
+        self.fill_from(map)?;
+        self.check_endpoint(self.start, PathFinderError::StartImpassable)?;
+        self.begin();
+        while self.distance_step_in(map) {}
+
+        Ok(())
+    }
+
+    /// The distance from start to (col, row) after a distance map, or `None` if the cell is
+    /// unreachable or off the grid.
+    pub fn distance(&self, col: i32, row: i32) -> Option<i32> {
+        self.cell_index(col, row)
+            .ok()
+            .map(|index| self.g_score[index])
+            .filter(|&g_score| g_score != PATH_FINDER_UNREACHABLE)
+    }
+
+    pub fn get_heuristic_score(&self, col: i32, row: i32) -> i32 {
+        self.cell_index(col, row)
+            .map_or(0, |index| self.f_score[index])
+    }
+
+    fn has_state(&self, col: i32, row: i32, flag: u8) -> u8 {
+        self.cell_index(col, row)
+            .map_or(0, |index| (self.state[index] & flag == flag) as u8)
+    }
+
+    pub fn is_passable(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x1)
+    }
+
+    pub fn is_closed(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x4)
+    }
+
+    pub fn is_open(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x2)
+    }
+
+    pub fn is_path(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x8)
+    }
+
+    pub fn is_goal(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x10)
+    }
+
+    /// Sets whether (col, row) is passable, leaving the rest of its state alone.
+    pub fn set_passable(
+        &mut self,
+        col: i32,
+        row: i32,
+        passable: bool,
+    ) -> Result<(), PathFinderError> {
+        let index = self.cell_index(col, row)?;
+        if passable {
+            self.state[index] |= 0x1;
+        } else {
+            self.state[index] &= !0x1;
+        }
+        Ok(())
+    }
+
+    /// Marks (col, row) as one of the goals of [`find_nearest`](Self::find_nearest).
+    pub fn set_goal(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        let index = self.cell_index(col, row)?;
+        self.state[index] |= 0x10;
+        Ok(())
+    }
+
+    pub fn clear_goals(&mut self) {
+        self.state
+            .as_mut()
+            .iter_mut()
+            .for_each(|state| *state &= !0x10);
+    }
+
+    pub fn is_source(&self, col: i32, row: i32) -> u8 {
+        self.has_state(col, row, 0x20)
+    }
+
+    /// Adds (col, row) to the sources of [`find_from_sources`](Self::find_from_sources), as if
+    /// reaching it cost `g_score`. Sources last until [`clear_path`](Self::clear_path).
+    pub fn add_source(&mut self, col: i32, row: i32, g_score: i32) -> Result<(), PathFinderError> {
+        let index = self.cell_index(col, row)?;
+        self.state[index] |= 0x20;
+        self.parents[index] = index as i32;
+        self.g_score[index] = g_score;
+        Ok(())
+    }
+
+    pub fn is_start(&self, col: i32, row: i32) -> u8 {
+        self.cell_index(col, row)
+            .map_or(0, |index| (index as i32 == self.start) as u8)
+    }
+
+    pub fn is_end(&self, col: i32, row: i32) -> u8 {
+        self.cell_index(col, row)
+            .map_or(0, |index| (index as i32 == self.end) as u8)
+    }
+
+    /// The cells of the found path ordered from start to end, both included. Empty if no path was
+    /// found.
+    pub fn path(&self) -> Vec<[i32; 2]> {
+        if self.has_path != 1 {
+            return Vec::new();
+        }
+
+        /* Sources are their own parents, see `add_source` */
+        let start = self.start;
+        let mut path: Vec<_> = iter::successors(Some(self.end), |&index| {
+            let parent = self.parents[index as usize];
+            Some(parent).filter(|&parent| index != start && parent != index)
+        })
+        .take(self.size())
+        .map(|index| self.position(index as usize))
+        .collect();
+        path.reverse();
+        path
+    }
+
+    /// Copies the first cells of the found path into `buffer`. Returns the length of the whole
+    /// path, which may exceed the length of `buffer`.
+    pub fn write_path(&self, buffer: &mut [[i32; 2]]) -> usize {
+        let path = self.path();
+        buffer
+            .iter_mut()
+            .zip(&path)
+            .for_each(|(cell, &position)| *cell = position);
+        path.len()
+    }
+
+    pub fn set_start(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        self.start = self.cell_index(col, row)? as i32;
+        Ok(())
+    }
+
+    pub fn set_end(&mut self, col: i32, row: i32) -> Result<(), PathFinderError> {
+        self.end = self.cell_index(col, row)? as i32;
+        Ok(())
+    }
+
+    pub fn clear_path(&mut self) {
+        let mut i: i32 = 0;
+        i = 0 as c_int;
+        while i < self.state.as_ref().len() as c_int {
+            self.state[i as usize] = (self.state[i as usize] as c_int
+                & !(0x2 as c_int | 0x4 as c_int | 0x8 as c_int | 0x20 as c_int))
+                as u8;
+            self.parents[i as usize] = 0 as c_int;
+            self.g_score[i as usize] = 0 as c_int;
+            self.f_score[i as usize] = 0 as c_int;
+            i += 1
+        }
+        self.open_set_size = 0 as c_int;
+        self.has_path = 0 as c_int as u8;
+    }
+
+    pub fn initialize(&mut self) {
+        let mut i: i32 = 0;
+        i = 0 as c_int;
+        while i < self.state.as_ref().len() as c_int {
+            self.parents[i as usize] = 0 as c_int;
+            self.g_score[i as usize] = 0 as c_int;
+            self.f_score[i as usize] = 0 as c_int;
+            self.state[i as usize] = 0x1 as c_int as u8;
+            i += 1
+        }
+        self.rows = 0 as c_int;
+        self.cols = 0 as c_int;
+        self.start = 0 as c_int;
+        self.end = 0 as c_int;
+        self.open_set_size = 0 as c_int;
+        self.movement = Movement::FourConnected;
+        self.orthogonal_cost = 1 as c_int;
+        self.diagonal_cost = 1 as c_int;
+        self.corner_cutting = CornerCutting::Always;
+        self.heuristic = HeuristicKind::Auto;
+        self.heuristic_weight = 1.0;
+        self.has_path = 0 as c_int as u8;
+        self.path_weight = 1.0;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_fill(path_finder: &mut PathFinder) -> c_int {
+    status(path_finder.fill())
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_begin(path_finder: &mut PathFinder) {
+    path_finder.begin()
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_step(path_finder: &mut PathFinder, data: *mut c_void) -> u8 {
+    path_finder.find_step(data)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find(path_finder: &mut PathFinder, data: *mut c_void) -> c_int {
+    status(path_finder.find(data).map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_get_heuristic_score(
+    path_finder: &mut PathFinder,
+    col: i32,
+    row: i32,
+) -> i32 {
+    path_finder.get_heuristic_score(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_passable(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_passable(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_closed(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_closed(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_open(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_open(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_path(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_path(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_start(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_start(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_end(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_end(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_set_start(path_finder: &mut PathFinder, col: i32, row: i32) -> c_int {
+    status(path_finder.set_start(col, row))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_set_end(path_finder: &mut PathFinder, col: i32, row: i32) -> c_int {
+    status(path_finder.set_end(col, row))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_bidirectional(
+    path_finder: &mut PathFinder,
+    data: *mut c_void,
+) -> c_int {
+    status(path_finder.find_bidirectional(data).map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_any_angle(path_finder: &mut PathFinder) -> c_int {
+    status(path_finder.find_any_angle().map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_has_line_of_sight(
+    path_finder: &PathFinder,
+    col: i32,
+    row: i32,
+    to_col: i32,
+    to_row: i32,
+) -> u8 {
+    path_finder.line_of_sight([col, row], [to_col, to_row]) as u8
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_jump_points(path_finder: &mut PathFinder) -> c_int {
+    status(path_finder.find_jump_points().map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_nearest(
+    path_finder: &mut PathFinder,
+    data: *mut c_void,
+) -> c_int {
+    status(path_finder.find_nearest(data).map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_goal(path_finder: &PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_goal(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_set_goal(path_finder: &mut PathFinder, col: i32, row: i32) -> c_int {
+    status(path_finder.set_goal(col, row))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_clear_goals(path_finder: &mut PathFinder) {
+    path_finder.clear_goals()
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_find_from_sources(
+    path_finder: &mut PathFinder,
+    data: *mut c_void,
+) -> c_int {
+    status(path_finder.find_from_sources(data).map(drop))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_is_source(path_finder: &PathFinder, col: i32, row: i32) -> u8 {
+    path_finder.is_source(col, row)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_add_source(
+    path_finder: &mut PathFinder,
+    col: i32,
+    row: i32,
+    g_score: i32,
+) -> c_int {
+    status(path_finder.add_source(col, row, g_score))
+}
+
+/// Returns the index of the source that reached (col, row), or -1.
+#[no_mangle]
+pub extern "C" fn path_finder_get_source(path_finder: &PathFinder, col: i32, row: i32) -> i32 {
+    source_index(path_finder, col, row)
+}
+
+fn source_index<S: Storage>(path_finder: &PathFinderBase<S>, col: i32, row: i32) -> i32 {
+    path_finder
+        .source_of(col, row)
+        .map_or(-1, |[col, row]| path_finder.index(col, row) as i32)
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_distance_map_from_sources(
+    path_finder: &mut PathFinder,
+    data: *mut c_void,
+) -> c_int {
+    status(path_finder.distance_map_from_sources(data))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_distance_map(
+    path_finder: &mut PathFinder,
+    data: *mut c_void,
+) -> c_int {
+    status(path_finder.distance_map(data))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_get_distance(path_finder: &PathFinder, col: i32, row: i32) -> i32 {
+    path_finder
+        .distance(col, row)
+        .unwrap_or(PATH_FINDER_UNREACHABLE)
+}
+
+/// Writes the found path as `col, row` pairs into `buffer`, which has room for `capacity` cells.
+/// Returns the length of the whole path, so a null `buffer` with a `capacity` of 0 queries it.
+///
+/// # Safety
+///
+/// `buffer` must be null or valid for writing `2 * capacity` integers.
+#[no_mangle]
+pub unsafe extern "C" fn path_finder_get_path(
+    path_finder: &PathFinder,
+    buffer: *mut i32,
+    capacity: usize,
+) -> i32 {
+    write_path(path_finder, buffer, capacity)
+}
+
+unsafe fn write_path<S: Storage>(
+    path_finder: &PathFinderBase<S>,
+    buffer: *mut i32,
+    capacity: usize,
+) -> i32 {
+    let buffer: &mut [[i32; 2]] = if buffer.is_null() {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(buffer.cast(), capacity)
+    };
+
+    path_finder.write_path(buffer) as i32
+}
+
+/// Writes the binary save of the path finder into `buffer` if it has room for all of it.
+/// Returns the size of the save, so a null `buffer` with a `capacity` of 0 queries it.
+///
+/// # Safety
+///
+/// `buffer` must be null or valid for writing `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn path_finder_save(
+    path_finder: &PathFinder,
+    buffer: *mut u8,
+    capacity: usize,
+) -> usize {
+    save_into(path_finder, buffer, capacity)
+}
+
+unsafe fn save_into<S: Storage>(
+    path_finder: &PathFinderBase<S>,
+    buffer: *mut u8,
+    capacity: usize,
+) -> usize {
+    let save = path_finder.save_binary();
+    if !buffer.is_null() && save.len() <= capacity {
+        slice::from_raw_parts_mut(buffer, save.len()).copy_from_slice(&save);
+    }
+    save.len()
+}
+
+/// Loads a binary save written by `path_finder_save`, keeping the function pointers and `data`.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reading `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn path_finder_load(
+    path_finder: &mut PathFinder,
+    bytes: *const u8,
+    len: usize,
+) -> c_int {
+    load_from(path_finder, bytes, len)
+}
+
+unsafe fn load_from<S: Storage>(
+    path_finder: &mut PathFinderBase<S>,
+    bytes: *const u8,
+    len: usize,
+) -> c_int {
+    let bytes = if bytes.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(bytes, len)
+    };
+    status(path_finder.load_binary(bytes))
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_clear_path(path_finder: &mut PathFinder) {
+    path_finder.clear_path()
+}
+
+#[no_mangle]
+pub extern "C" fn path_finder_initialize(path_finder: &mut PathFinder) {
+    path_finder.initialize()
+}
+
+/// Generates the C entry points for a fixed-capacity [`PathFinder`], exported with the given
+/// symbol prefix (i.e. `path_finder_64_find` for `PathFinder<64>`).
+macro_rules! path_finder_extern {
+    ($module:ident, $alias:ident, $prefix:literal) => {
+        pub mod $module {
+            use super::{
+                load_from, save_into, source_index, status, write_path, $alias,
+                PATH_FINDER_UNREACHABLE,
+            };
+            use std::os::raw::{c_int, c_void};
+
+            #[export_name = concat!($prefix, "fill")]
+            pub extern "C" fn fill(path_finder: &mut $alias) -> c_int {
+                status(path_finder.fill())
+            }
+
+            #[export_name = concat!($prefix, "begin")]
+            pub extern "C" fn begin(path_finder: &mut $alias) {
+                path_finder.begin()
+            }
+
+            #[export_name = concat!($prefix, "find_step")]
+            pub extern "C" fn find_step(path_finder: &mut $alias, data: *mut c_void) -> u8 {
+                path_finder.find_step(data)
+            }
+
+            #[export_name = concat!($prefix, "find")]
+            pub extern "C" fn find(path_finder: &mut $alias, data: *mut c_void) -> c_int {
+                status(path_finder.find(data).map(drop))
+            }
+
+            #[export_name = concat!($prefix, "get_heuristic_score")]
+            pub extern "C" fn get_heuristic_score(
+                path_finder: &mut $alias,
+                col: i32,
+                row: i32,
+            ) -> i32 {
+                path_finder.get_heuristic_score(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_passable")]
+            pub extern "C" fn is_passable(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_passable(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_closed")]
+            pub extern "C" fn is_closed(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_closed(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_open")]
+            pub extern "C" fn is_open(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_open(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_path")]
+            pub extern "C" fn is_path(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_path(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_start")]
+            pub extern "C" fn is_start(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_start(col, row)
+            }
+
+            #[export_name = concat!($prefix, "is_end")]
+            pub extern "C" fn is_end(path_finder: &mut $alias, col: i32, row: i32) -> u8 {
+                path_finder.is_end(col, row)
+            }
+
+            #[export_name = concat!($prefix, "set_start")]
+            pub extern "C" fn set_start(path_finder: &mut $alias, col: i32, row: i32) -> c_int {
+                status(path_finder.set_start(col, row))
+            }
+
+            #[export_name = concat!($prefix, "set_end")]
+            pub extern "C" fn set_end(path_finder: &mut $alias, col: i32, row: i32) -> c_int {
+                status(path_finder.set_end(col, row))
+            }
+
+            #[export_name = concat!($prefix, "find_bidirectional")]
+            pub extern "C" fn find_bidirectional(
+                path_finder: &mut $alias,
+                data: *mut c_void,
+            ) -> c_int {
+                status(path_finder.find_bidirectional(data).map(drop))
+            }
+
+            #[export_name = concat!($prefix, "find_any_angle")]
+            pub extern "C" fn find_any_angle(path_finder: &mut $alias) -> c_int {
+                status(path_finder.find_any_angle().map(drop))
+            }
+
+            #[export_name = concat!($prefix, "has_line_of_sight")]
+            pub extern "C" fn has_line_of_sight(
+                path_finder: &$alias,
+                col: i32,
+                row: i32,
+                to_col: i32,
+                to_row: i32,
+            ) -> u8 {
+                path_finder.line_of_sight([col, row], [to_col, to_row]) as u8
+            }
+
+            #[export_name = concat!($prefix, "find_jump_points")]
+            pub extern "C" fn find_jump_points(path_finder: &mut $alias) -> c_int {
+                status(path_finder.find_jump_points().map(drop))
+            }
+
+            #[export_name = concat!($prefix, "find_nearest")]
+            pub extern "C" fn find_nearest(path_finder: &mut $alias, data: *mut c_void) -> c_int {
+                status(path_finder.find_nearest(data).map(drop))
+            }
+
+            #[export_name = concat!($prefix, "is_goal")]
+            pub extern "C" fn is_goal(path_finder: &$alias, col: i32, row: i32) -> u8 {
+                path_finder.is_goal(col, row)
+            }
+
+            #[export_name = concat!($prefix, "set_goal")]
+            pub extern "C" fn set_goal(path_finder: &mut $alias, col: i32, row: i32) -> c_int {
+                status(path_finder.set_goal(col, row))
+            }
+
+            #[export_name = concat!($prefix, "clear_goals")]
+            pub extern "C" fn clear_goals(path_finder: &mut $alias) {
+                path_finder.clear_goals()
+            }
+
+            #[export_name = concat!($prefix, "find_from_sources")]
+            pub extern "C" fn find_from_sources(
+                path_finder: &mut $alias,
+                data: *mut c_void,
+            ) -> c_int {
+                status(path_finder.find_from_sources(data).map(drop))
+            }
+
+            #[export_name = concat!($prefix, "is_source")]
+            pub extern "C" fn is_source(path_finder: &$alias, col: i32, row: i32) -> u8 {
+                path_finder.is_source(col, row)
+            }
+
+            #[export_name = concat!($prefix, "add_source")]
+            pub extern "C" fn add_source(
+                path_finder: &mut $alias,
+                col: i32,
+                row: i32,
+                g_score: i32,
+            ) -> c_int {
+                status(path_finder.add_source(col, row, g_score))
+            }
+
+            #[export_name = concat!($prefix, "get_source")]
+            pub extern "C" fn get_source(path_finder: &$alias, col: i32, row: i32) -> i32 {
+                source_index(path_finder, col, row)
+            }
+
+            #[export_name = concat!($prefix, "distance_map_from_sources")]
+            pub extern "C" fn distance_map_from_sources(
+                path_finder: &mut $alias,
+                data: *mut c_void,
+            ) -> c_int {
+                status(path_finder.distance_map_from_sources(data))
+            }
+
+            #[export_name = concat!($prefix, "distance_map")]
+            pub extern "C" fn distance_map(path_finder: &mut $alias, data: *mut c_void) -> c_int {
+                status(path_finder.distance_map(data))
+            }
+
+            #[export_name = concat!($prefix, "get_distance")]
+            pub extern "C" fn get_distance(path_finder: &$alias, col: i32, row: i32) -> i32 {
+                path_finder
+                    .distance(col, row)
+                    .unwrap_or(PATH_FINDER_UNREACHABLE)
+            }
+
+            /// # Safety
+            ///
+            /// `buffer` must be null or valid for writing `2 * capacity` integers.
+            #[export_name = concat!($prefix, "get_path")]
+            pub unsafe extern "C" fn get_path(
+                path_finder: &$alias,
+                buffer: *mut i32,
+                capacity: usize,
+            ) -> i32 {
+                write_path(path_finder, buffer, capacity)
+            }
+
+            /// # Safety
+            ///
+            /// `buffer` must be null or valid for writing `capacity` bytes.
+            #[export_name = concat!($prefix, "save")]
+            pub unsafe extern "C" fn save(
+                path_finder: &$alias,
+                buffer: *mut u8,
+                capacity: usize,
+            ) -> usize {
+                save_into(path_finder, buffer, capacity)
+            }
+
+            /// # Safety
+            ///
+            /// `bytes` must be valid for reading `len` bytes.
+            #[export_name = concat!($prefix, "load")]
+            pub unsafe extern "C" fn load(
+                path_finder: &mut $alias,
+                bytes: *const u8,
+                len: usize,
+            ) -> c_int {
+                load_from(path_finder, bytes, len)
+            }
+
+            #[export_name = concat!($prefix, "clear_path")]
+            pub extern "C" fn clear_path(path_finder: &mut $alias) {
+                path_finder.clear_path()
+            }
+
+            #[export_name = concat!($prefix, "initialize")]
+            pub extern "C" fn initialize(path_finder: &mut $alias) {
+                path_finder.initialize()
+            }
+        }
+    };
+}
+
+pub type PathFinder64 = PathFinder<64>;
+pub type PathFinder256 = PathFinder<256>;
+pub type PathFinder4096 = PathFinder<4096>;
+
+path_finder_extern!(path_finder_64, PathFinder64, "path_finder_64_");
+path_finder_extern!(path_finder_256, PathFinder256, "path_finder_256_");
+path_finder_extern!(path_finder_4096, PathFinder4096, "path_finder_4096_");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::{mem, sync::OnceLock};
+
+    #[test]
+    fn open_set_is_empty() {
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 5,
+            rows: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(path_finder_open_set_is_empty(&path_finder), 1);
+
+        path_finder_open_set_push(&mut path_finder, 7);
+        assert_eq!(path_finder_open_set_is_empty(&path_finder), 0);
+    }
+
+    #[test]
+    fn lowest_in_open_set() {
+        let path_finder: PathFinder = PathFinder::default();
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 0);
+
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 5,
+            rows: 4,
+            ..Default::default()
+        };
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 0);
+
+        path_finder.f_score[3] = -5;
+        path_finder_open_set_push(&mut path_finder, 3);
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 3);
+
+        path_finder.f_score[6] = -9;
+        path_finder_open_set_push(&mut path_finder, 6);
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 6);
+
+        path_finder.f_score[11] = -7;
+        path_finder_open_set_push(&mut path_finder, 11);
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 6);
+
+        path_finder.f_score[11] = -10;
+        path_finder_open_set_push(&mut path_finder, 11);
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 11);
+
+        path_finder.f_score[2] = -10;
+        path_finder_open_set_push(&mut path_finder, 2);
+        assert_eq!(path_finder_lowest_in_open_set(&path_finder), 2);
+
+        assert_eq!(path_finder_open_set_pop(&mut path_finder), 2);
+        assert_eq!(path_finder_open_set_pop(&mut path_finder), 11);
+        assert_eq!(path_finder_open_set_pop(&mut path_finder), 6);
+        assert_eq!(path_finder_open_set_pop(&mut path_finder), 3);
+        assert_eq!(path_finder_open_set_is_empty(&path_finder), 1);
+        assert!(path_finder.state.iter().all(|&state| state & 0x2 == 0));
+    }
+
+    #[test]
+    fn reconstruct_path() {
+        /*
+         * Representation
+         *
+         * /-----\
+         * | >>>v|
+         * | S v<|
+         * |v<<< |
+         * |>>>E |
+         * \-----/
+         */
+
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 5,
+            rows: 4,
+            start: 6,
+            end: 18,
+            ..Default::default()
+        };
+
+        let parents = &mut path_finder.parents;
         parents[18] = 17;
         parents[17] = 16;
-        parents[16] = 15;
-        parents[15] = 10;
-        parents[10] = 11;
-        parents[11] = 12;
-        parents[12] = 13;
-        parents[13] = 8;
-        parents[8] = 9;
-        parents[9] = 4;
-        parents[4] = 3;
-        parents[3] = 2;
-        parents[2] = 1;
-        parents[1] = 6;
+        parents[16] = 15;
+        parents[15] = 10;
+        parents[10] = 11;
+        parents[11] = 12;
+        parents[12] = 13;
+        parents[13] = 8;
+        parents[8] = 9;
+        parents[9] = 4;
+        parents[4] = 3;
+        parents[3] = 2;
+        parents[2] = 1;
+        parents[1] = 6;
+
+        path_finder_reconstruct_path(&mut path_finder);
+        path_finder
+            .parents
+            .iter()
+            .copied()
+            .zip(path_finder.state.iter().copied())
+            .enumerate()
+            .for_each(|(index, (parent_index, state))| {
+                if parent_index == 0 || index as i32 == path_finder.end {
+                    assert_eq!(state, 0);
+                } else {
+                    assert_eq!(state, 0x8);
+                }
+            });
+    }
+
+    #[test]
+    fn fill() {
+        let mut path_finder = PathFinder {
+            cols: 5,
+            rows: 4,
+            ..Default::default()
+        };
+        const IMPASSABLES: [usize; 6] = [1, 2, 3, 5, 8, 13];
+        extern "C" fn fill_func(path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+            let index = (row * path_finder.cols + col).try_into().unwrap();
+            IMPASSABLES.binary_search(&index).is_err() as u8
+        }
+        path_finder.fill_func = Some(fill_func);
+
+        let size = path_finder.cols * path_finder.rows;
+        let test_states = move |path_finder: &PathFinder| {
+            path_finder
+                .state
+                .iter()
+                .take(size as usize)
+                .copied()
+                .enumerate()
+                .for_each(|(index, state)| {
+                    if IMPASSABLES.binary_search(&index).is_err() {
+                        assert_eq!(state, 0x1);
+                    } else {
+                        assert_eq!(state, 0);
+                    }
+                })
+        };
+
+        path_finder_fill(&mut path_finder);
+        test_states(&path_finder);
+
+        path_finder.state.iter_mut().for_each(|state| *state = 0x1);
+        path_finder_fill(&mut path_finder);
+        test_states(&path_finder);
+    }
+
+    #[test]
+    fn begin() {
+        let mut path_finder = PathFinder {
+            start: 13,
+            ..Default::default()
+        };
+
+        path_finder_begin(&mut path_finder);
+        assert_eq!(path_finder.state[13], 0x2);
+        path_finder
+            .state
+            .iter()
+            .copied()
+            .take(13)
+            .chain(path_finder.state.iter().copied().skip(14))
+            .for_each(|state| assert_eq!(state, 0));
+    }
+
+    #[test]
+    fn find_step_at_end() {
+        /*
+         * Representation
+         *
+         * /-----\
+         * |     |
+         * | S   |
+         * | v   |
+         * | >>E |
+         * \-----/
+         */
+
+        let mut path_finder = PathFinder {
+            cols: 5,
+            rows: 4,
+            start: 6,
+            end: 18,
+            ..Default::default()
+        };
+
+        const PATH_INDICES: [usize; 4] = [11, 16, 17, 18];
+        let parents = &mut path_finder.parents;
+        parents[11] = 6;
+        parents[16] = 11;
+        parents[17] = 16;
+        parents[18] = 17;
+
+        path_finder.f_score[18] = -10;
+        PATH_INDICES.iter().for_each(|&index| {
+            path_finder_open_set_push(&mut path_finder, index.try_into().unwrap())
+        });
+
+        let run = path_finder_find_step(&mut path_finder, null_mut());
+        assert_eq!(run, 0);
+        assert_eq!(path_finder.has_path, 1);
+        path_finder
+            .state
+            .iter()
+            .take((path_finder.cols * path_finder.rows).try_into().unwrap())
+            .copied()
+            .enumerate()
+            .for_each(|(index, state)| {
+                if path_finder.end == index.try_into().unwrap() {
+                    assert_eq!(state, 0x2);
+                } else if PATH_INDICES.binary_search(&index).is_ok() {
+                    assert_eq!(state, 0xa);
+                } else {
+                    assert_eq!(state, 0);
+                }
+            });
+    }
+
+    #[test]
+    fn find_step_empty_set() {
+        let mut path_finder = PathFinder {
+            cols: 5,
+            rows: 4,
+            start: 6,
+            end: 18,
+            ..Default::default()
+        };
+        path_finder.f_score[18] = -10;
+
+        let run = path_finder_find_step(&mut path_finder, null_mut());
+        assert_eq!(run, 0);
+        assert_eq!(path_finder.has_path, 0);
+        assert!(path_finder.state.iter().copied().all(|state| state == 0));
+    }
+
+    #[test]
+    fn find_step() {
+        let mut path_finder = create_complex_map();
+        path_finder_fill(&mut path_finder);
+        path_finder_begin(&mut path_finder);
+
+        check_next_step(&mut path_finder);
+        assert_eq!(
+            path_finder.cell(6, 5).unwrap(),
+            Cell {
+                state: 5,
+                ..Default::default()
+            }
+        );
+
+        for _step in 0..312 {
+            assert!(check_next_step(&mut path_finder));
+        }
+
+        assert!(!check_next_step(&mut path_finder));
+    }
+
+    #[test]
+    fn find_small_capacity() {
+        let map: AsciiMap = r"
+/----\
+|S#  |
+|v# #|
+|v>>v|
+|  #E|
+\----/
+"
+        .parse()
+        .unwrap();
+
+        let mut path_finder = PathFinder::<16> {
+            cols: map.cols,
+            rows: map.rows,
+            ..Default::default()
+        };
+        assert!(mem::size_of::<PathFinder<16>>() < mem::size_of::<PathFinder>() / 32);
+
+        path_finder
+            .fill_with(|col, row| map.passable[(row * map.cols + col) as usize])
+            .unwrap();
+        let ([start_col, start_row], [end_col, end_row]) = (map.start.unwrap(), map.end.unwrap());
+        path_finder.set_start(start_col, start_row).unwrap();
+        path_finder.set_end(end_col, end_row).unwrap();
+        path_finder.find(null_mut()).unwrap();
+
+        assert_eq!(path_finder.has_path, 1);
+        assert_eq!(path_finder.g_score[15], 6);
+        const PATH: [[i32; 2]; 5] = [[0, 1], [0, 2], [1, 2], [2, 2], [3, 2]];
+        for row in 0..4 {
+            for col in 0..4 {
+                let expected = PATH.contains(&[col, row]) as u8;
+                assert_eq!(path_finder.is_path(col, row), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn path() {
+        /*
+         * Representation
+         *
+         * /----\
+         * |S#  |
+         * |v# #|
+         * |v>>v|
+         * |  #E|
+         * \----/
+         */
+
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 4,
+            rows: 4,
+            end: 15,
+            ..Default::default()
+        };
+        assert!(path_finder.path().is_empty());
+
+        path_finder
+            .find_in(&mut |col, row| ![[1, 0], [1, 1], [3, 1], [2, 3]].contains(&[col, row]))
+            .unwrap();
+        const PATH: [[i32; 2]; 7] = [[0, 0], [0, 1], [0, 2], [1, 2], [2, 2], [3, 2], [3, 3]];
+        assert_eq!(path_finder.path(), PATH);
+
+        let mut buffer = [[-1; 2]; 8];
+        assert_eq!(path_finder.write_path(&mut buffer[..3]), 7);
+        assert_eq!(buffer[..3], PATH[..3]);
+        assert_eq!(buffer[3], [-1; 2]);
+
+        let mut buffer = [-1; 16];
+        let length = unsafe { path_finder_get_path(&path_finder, buffer.as_mut_ptr(), 8) };
+        assert_eq!(length, 7);
+        assert_eq!(buffer[..14], *PATH.concat());
+        assert_eq!(buffer[14..], [-1; 2]);
+        assert_eq!(
+            unsafe { path_finder_get_path(&path_finder, null_mut(), 0) },
+            7
+        );
+
+        path_finder.set_end(0, 0).unwrap();
+        path_finder.find(null_mut()).unwrap();
+        assert_eq!(path_finder.path(), [[0, 0]]);
+
+        /* An unreachable end at cell 0, which an empty open set must not report as found */
+        let mut walled: PathFinder = PathFinder {
+            cols: 4,
+            rows: 4,
+            start: 15,
+            ..Default::default()
+        };
+        assert_eq!(
+            walled.find_in(&mut |col, row| ![[1, 0], [0, 1]].contains(&[col, row])),
+            Ok(false)
+        );
+        assert!(walled.path().is_empty());
+
+        /* A cell that is its own parent ends the path, as for sources */
+        walled.has_path = 1;
+        assert_eq!(walled.path(), [[0, 0]]);
+    }
+
+    #[test]
+    fn distance_map() {
+        let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder_fill(&mut path_finder);
+
+        let mut distances = path_finder;
+        assert_eq!(
+            path_finder_distance_map(&mut distances, null_mut()),
+            PATH_FINDER_OK
+        );
+        assert_eq!(distances.distance(6, 5), Some(0));
+        assert_eq!(path_finder_get_distance(&distances, 8, 3), -1);
+        assert_eq!(distances.distance(25, 0), None);
+
+        let mut unreachable = 0;
+        for row in 0..path_finder.rows {
+            for col in 0..path_finder.cols {
+                let mut search = path_finder;
+                search.set_end(col, row).unwrap();
+                match search.find(null_mut()) {
+                    Ok(true) => assert_eq!(
+                        distances.distance(col, row),
+                        Some(search.g_score[search.end as usize])
+                    ),
+                    _ => {
+                        unreachable += 1;
+                        assert_eq!(distances.distance(col, row), None);
+                    }
+                }
+            }
+        }
+        assert!(unreachable > 0);
+
+        /*
+         * Representation
+         *
+         * /-----\
+         * |S333 |
+         * |  #  |
+         * \-----/
+         */
+
+        let mut path_finder = HeapPathFinder::new(5, 2).unwrap();
+        path_finder
+            .distance_map_in(&mut FnMap::new(
+                |col, row| [col, row] != [2, 1],
+                |col, row| if row == 0 && col > 0 && col < 4 { 3 } else { 1 },
+            ))
+            .unwrap();
+        let distances: Vec<_> = (0..10)
+            .map(|index| path_finder.distance(index % 5, index / 5))
+            .collect();
+        assert_eq!(
+            distances,
+            [
+                Some(0),
+                Some(3),
+                Some(6),
+                Some(9),
+                Some(10),
+                Some(1),
+                Some(2),
+                None,
+                Some(10),
+                Some(11)
+            ]
+        );
+    }
+
+    #[test]
+    fn find_nearest() {
+        let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder_fill(&mut path_finder);
+
+        let mut distances = path_finder;
+        distances.distance_map(null_mut()).unwrap();
+
+        let mut nearest = path_finder;
+        assert_eq!(nearest.find_nearest(null_mut()), Ok(None));
+        for &[col, row] in &[[20, 11], [3, 14], [24, 0], [8, 4]] {
+            path_finder_set_goal(&mut nearest, col, row);
+        }
+        assert_eq!(nearest.is_goal(8, 4), 1);
+        assert_eq!(nearest.find_nearest(null_mut()), Ok(Some([24, 0])));
+        assert_eq!(nearest.end, 24);
+        assert_eq!(nearest.g_score[24], distances.distance(24, 0).unwrap());
+        assert_eq!(nearest.path().first(), Some(&[6, 5]));
+        assert_eq!(nearest.path().last(), Some(&[24, 0]));
+
+        /* Too many goals to estimate, so the search runs as Dijkstra */
+        let mut dijkstra = path_finder;
+        for col in 0..25 {
+            dijkstra.set_goal(col, 15).unwrap();
+        }
+        assert_eq!(path_finder_find_nearest(&mut dijkstra, null_mut()), 0);
+        let closest = (0..25)
+            .filter_map(|col| distances.distance(col, 15))
+            .min()
+            .unwrap();
+        assert_eq!(dijkstra.g_score[dijkstra.end as usize], closest);
+        assert_eq!(dijkstra.is_goal(dijkstra.end % 25, dijkstra.end / 25), 1);
+
+        dijkstra.clear_goals();
+        assert!(dijkstra.state.iter().all(|&state| state & 0x10 == 0));
+        dijkstra.clear_path();
+        dijkstra.set_goal(3, 14).unwrap();
+        assert_eq!(dijkstra.find_nearest(null_mut()), Ok(Some([3, 14])));
+
+        /* A single goal searches as find does, with the same heuristic_func and weight */
+        extern "C" fn doubled(path_finder: &PathFinder, col: i32, row: i32) -> i32 {
+            let [end_col, end_row] = path_finder.position(path_finder.end as usize);
+            2 * ((end_col - col).abs() + (end_row - row).abs())
+        }
+        let mut single = path_finder;
+        single.heuristic_func = Some(doubled);
+        single.heuristic_weight = 1.5;
+        let mut goal = single;
+        single.set_end(20, 11).unwrap();
+        assert_eq!(single.find(null_mut()), Ok(true));
+        goal.set_goal(20, 11).unwrap();
+        assert_eq!(goal.find_nearest(null_mut()), Ok(Some([20, 11])));
+        assert_eq!(goal.path_weight, 1.5);
+        assert_eq!(goal.path(), single.path());
+        assert_eq!(goal.f_score, single.f_score);
+        assert!(goal
+            .state
+            .iter()
+            .zip(single.state.iter())
+            .all(|(goal, single)| goal & 0xe == single & 0xe));
+    }
+
+    #[test]
+    fn find_from_sources() {
+        let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder_fill(&mut path_finder);
+
+        const SOURCES: [([i32; 2], i32); 3] = [([6, 5], 6), ([24, 15], 0), ([0, 0], 2)];
+        let distances: Vec<_> = SOURCES
+            .iter()
+            .map(|&([col, row], _)| {
+                let mut distances = path_finder;
+                distances.set_start(col, row).unwrap();
+                distances.distance_map(null_mut()).unwrap();
+                distances
+            })
+            .collect();
+        let nearest = |col, row| {
+            SOURCES
+                .iter()
+                .zip(&distances)
+                .filter_map(|(&(_, g_score), distances)| {
+                    Some(g_score + distances.distance(col, row)?)
+                })
+                .min()
+        };
+
+        let mut sources = path_finder;
+        assert_eq!(
+            sources.find_from_sources(null_mut()),
+            Err(PathFinderError::StartImpassable)
+        );
+        for &([col, row], g_score) in &SOURCES {
+            path_finder_add_source(&mut sources, col, row, g_score);
+        }
+        assert_eq!(sources.is_source(24, 15), 1);
+
+        let mut search = sources;
+        assert_eq!(search.find_from_sources(null_mut()), Ok(Some([24, 15])));
+        assert_eq!(search.start, 24 + 15 * 25);
+        assert_eq!(
+            search.g_score[search.end as usize],
+            nearest(20, 11).unwrap()
+        );
+        assert_eq!(search.path().first(), Some(&[24, 15]));
+        assert_eq!(search.path().last(), Some(&[20, 11]));
+        assert_eq!(search.is_path(24, 15), 0);
+        assert_eq!(search.is_path(24, 14), 1);
+
+        search.clear_path();
+        assert!(search.state.iter().all(|&state| state & 0x20 == 0));
+        search.add_source(0, 0, 0).unwrap();
+        assert_eq!(search.find_from_sources(null_mut()), Ok(Some([0, 0])));
+        assert_eq!(
+            search.g_score[search.end as usize],
+            distances[2].distance(20, 11).unwrap()
+        );
+
+        let mut territories = sources;
+        assert_eq!(
+            path_finder_distance_map_from_sources(&mut territories, null_mut()),
+            PATH_FINDER_OK
+        );
+        for row in 0..path_finder.rows {
+            for col in 0..path_finder.cols {
+                assert_eq!(territories.distance(col, row), nearest(col, row));
+
+                let source = territories.source_of(col, row);
+                let from_source = SOURCES
+                    .iter()
+                    .zip(&distances)
+                    .find(|&(&(cell, _), _)| Some(cell) == source)
+                    .and_then(|(&(_, g_score), distances)| {
+                        Some(g_score + distances.distance(col, row)?)
+                    });
+                assert_eq!(from_source, nearest(col, row));
+            }
+        }
+        assert_eq!(path_finder_get_source(&territories, 8, 3), -1);
+        assert_eq!(path_finder_get_source(&territories, 6, 6), 6 + 5 * 25);
+    }
+
+    #[test]
+    fn heap_path_finder() {
+        assert!(HeapPathFinder::new(0, 4).is_err());
+        assert!(HeapPathFinder::new(4, -1).is_err());
+        assert!(HeapPathFinder::new(65536, 65536).is_err());
+        assert!(HeapPathFinder::new(i32::MAX, 2).is_err());
+
+        let mut path_finder = HeapPathFinder::new(80, 40).unwrap();
+        assert_eq!(path_finder.state.len(), 3200);
+        assert!(path_finder.state.iter().all(|&state| state == 0x1));
+
+        /* A wall on column 40 with a gap on the last row */
+        extern "C" fn wall(_path_finder: &mut HeapPathFinder, col: i32, row: i32) -> u8 {
+            (col != 40 || row == 39) as u8
+        }
+        path_finder.fill_func = Some(wall);
+        path_finder.fill().unwrap();
+        path_finder.set_start(0, 0).unwrap();
+        path_finder.set_end(79, 0).unwrap();
+        path_finder.find(null_mut()).unwrap();
+
+        assert_eq!(path_finder.has_path, 1);
+        assert_eq!(path_finder.g_score[79], 79 + 2 * 39);
+        assert_eq!(path_finder.is_path(40, 39), 1);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            HeapPathFinder::new(0, 4).unwrap_err(),
+            PathFinderError::InvalidSize
+        );
+        assert_eq!(
+            HeapPathFinder::new(65536, 65536).unwrap_err(),
+            PathFinderError::GridTooLarge
+        );
+
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 4,
+            rows: 3,
+            ..Default::default()
+        };
+        assert_eq!(path_finder.fill(), Err(PathFinderError::MissingFillFunc));
+        assert_eq!(
+            path_finder_fill(&mut path_finder),
+            PathFinderError::MissingFillFunc.code()
+        );
+        assert_eq!(
+            path_finder.cell(4, 0).unwrap_err(),
+            PathFinderError::OutOfBounds
+        );
+        assert_eq!(
+            path_finder.cell(0, -1).unwrap_err(),
+            PathFinderError::OutOfBounds
+        );
+        assert_eq!(
+            path_finder.get(12).unwrap_err(),
+            PathFinderError::OutOfBounds
+        );
+        assert_eq!(path_finder.set_end(0, 3), Err(PathFinderError::OutOfBounds));
+        assert_eq!(
+            path_finder_set_start(&mut path_finder, -1, 0),
+            PathFinderError::OutOfBounds.code()
+        );
+        assert_eq!(path_finder.is_passable(-1, 0), 0);
+        assert_eq!(path_finder.get_heuristic_score(0, 3), 0);
+
+        extern "C" fn wall(_path_finder: &mut PathFinder, col: i32, _row: i32) -> u8 {
+            (col != 3) as u8
+        }
+        path_finder.fill_func = Some(wall);
+        assert_eq!(path_finder_fill(&mut path_finder), PATH_FINDER_OK);
+        path_finder.set_end(3, 2).unwrap();
+        assert_eq!(
+            path_finder.find(null_mut()),
+            Err(PathFinderError::EndImpassable)
+        );
+        assert_eq!(path_finder.has_path, 0);
+        assert!(path_finder.state.iter().all(|&state| state & 0x6 == 0));
 
-        path_finder_reconstruct_path(&mut path_finder);
-        path_finder
-            .parents
-            .iter()
-            .copied()
-            .zip(path_finder.state.iter().copied())
-            .enumerate()
-            .for_each(|(index, (parent_index, state))| {
-                if parent_index == 0 || index as i32 == path_finder.end {
-                    assert_eq!(state, 0);
-                } else {
-                    assert_eq!(state, 0x8);
-                }
-            });
+        path_finder.set_start(3, 0).unwrap();
+        assert_eq!(
+            path_finder_find(&mut path_finder, null_mut()),
+            PathFinderError::StartImpassable.code()
+        );
+        /* Off the grid, not the cell the row-major index would alias */
+        assert_eq!(path_finder.is_start(3, 0), 1);
+        assert_eq!(path_finder.is_start(-1, 1), 0);
+        assert_eq!(path_finder_is_end(&mut path_finder, 6, 1), 0);
+        assert_eq!(path_finder.is_end(i32::MAX, i32::MAX), 0);
+
+        path_finder.set_start(0, 0).unwrap();
+        path_finder.set_end(2, 2).unwrap();
+        assert_eq!(path_finder.find(null_mut()), Ok(true));
+        assert_eq!(
+            PathFinderError::StartImpassable.to_string(),
+            "start cell is impassable"
+        );
+
+        path_finder.rows = 0;
+        assert_eq!(
+            path_finder.find(null_mut()),
+            Err(PathFinderError::InvalidSize)
+        );
     }
 
     #[test]
-    fn fill() {
-        let mut path_finder = PathFinder {
-            cols: 5,
-            rows: 4,
-            ..Default::default()
-        };
-        const IMPASSABLES: [usize; 6] = [1, 2, 3, 5, 8, 13];
-        let fill_func = |path_finder: &mut PathFinder, col: i32, row: i32| -> u8 {
-            let index = (row * path_finder.cols + col).try_into().unwrap();
-            IMPASSABLES.binary_search(&index).is_err() as u8
-        };
-        path_finder.fill_func = Some(fill_func);
+    fn heuristic_weight() {
+        let mut rng = SmallRng::seed_from_u64(19);
+        let (mut optimal_closed, mut weighted_closed) = (0, 0);
+        for _map in 0..20 {
+            let mut path_finder = random_grid(
+                &mut rng,
+                [60, 40],
+                Movement::EightConnected,
+                [1, 4],
+                &[[0, 0], [59, 39]],
+            );
+            path_finder.set_end(59, 39).unwrap();
+
+            let search = |weight: f32| {
+                let mut search = path_finder.clone();
+                search.heuristic_weight = weight;
+                let found = search.find(null_mut()).unwrap();
+                assert_eq!(search.path_weight, weight);
+                let closed = search.state.iter().filter(|&&state| state & 0x5 == 0x5);
+                (found, search.g_score[2399], closed.count())
+            };
 
-        let size = path_finder.cols * path_finder.rows;
-        let test_states = move |path_finder: &PathFinder| {
-            path_finder
-                .state
-                .iter()
-                .take(size as usize)
-                .copied()
-                .enumerate()
-                .for_each(|(index, state)| {
-                    if IMPASSABLES.binary_search(&index).is_err() {
-                        assert_eq!(state, 0x1);
-                    } else {
-                        assert_eq!(state, 0);
-                    }
-                })
-        };
+            let (found, optimal, closed) = search(1.0);
+            optimal_closed += closed;
+            for &weight in &[1.5, 3.0] {
+                let (weighted_found, cost, closed) = search(weight);
+                assert_eq!(weighted_found, found);
+                if found {
+                    assert!(cost >= optimal && cost as f32 <= optimal as f32 * weight);
+                }
+                if weight == 1.5 {
+                    weighted_closed += closed;
+                }
+            }
+        }
+        assert!(weighted_closed * 2 < optimal_closed);
 
-        path_finder_fill(&mut path_finder);
-        test_states(&path_finder);
+        /* Weights below 1 or NaN bound the path as 1 does */
+        let mut path_finder = create_complex_map();
+        path_finder.fill().unwrap();
+        let mut optimal = path_finder;
+        optimal.find(null_mut()).unwrap();
+        for &weight in &[0.5, 0.0, -1.0, f32::NAN] {
+            let mut search = path_finder;
+            search.heuristic_weight = weight;
+            assert_eq!(search.find(null_mut()), Ok(true));
+            assert_eq!(search.path_weight, 1.0);
+            assert_eq!(search.path(), optimal.path());
+        }
 
-        path_finder.state.iter_mut().for_each(|state| *state = 0x1);
-        path_finder_fill(&mut path_finder);
-        test_states(&path_finder);
+        let mut path_finder = HeapPathFinder::new(8, 8).unwrap();
+        path_finder.heuristic_weight = 2.0;
+        path_finder.set_end(7, 7).unwrap();
+        assert_eq!(path_finder.find_jump_points(), Ok(true));
+        assert_eq!(path_finder.path_weight, 1.0);
     }
 
     #[test]
-    fn begin() {
-        let mut path_finder = PathFinder {
-            start: 13,
-            ..Default::default()
-        };
+    fn find_large_map() {
+        let mut path_finder = HeapPathFinder::new(512, 512).unwrap();
 
-        path_finder_begin(&mut path_finder);
-        assert_eq!(path_finder.state[13], 0x2);
-        path_finder
-            .state
-            .iter()
-            .copied()
-            .take(13)
-            .chain(path_finder.state.iter().copied().skip(14))
-            .for_each(|state| assert_eq!(state, 0));
+        /* Horizontal walls every 64 rows, alternating the gap between the two sides */
+        extern "C" fn walls(_path_finder: &mut HeapPathFinder, col: i32, row: i32) -> u8 {
+            let gap = if row / 64 % 2 == 0 { 511 } else { 0 };
+            (row % 64 != 63 || col == gap) as u8
+        }
+        path_finder.fill_func = Some(walls);
+        path_finder.fill().unwrap();
+        path_finder.set_start(0, 0).unwrap();
+        path_finder.set_end(0, 511).unwrap();
+        path_finder.find(null_mut()).unwrap();
+
+        assert_eq!(path_finder.has_path, 1);
+        assert_eq!(path_finder.g_score[511 * 512], 511 + 8 * 511);
     }
 
     #[test]
-    fn find_step_at_end() {
+    fn find_eight_connected() {
         /*
          * Representation
          *
          * /-----\
+         * |S    |
          * |     |
-         * | S   |
-         * | v   |
-         * | >>E |
+         * |  #  |
+         * |     |
+         * |    E|
          * \-----/
          */
 
-        let mut path_finder = PathFinder {
+        extern "C" fn pillar(_path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
+            ([col, row] != [2, 2]) as u8
+        }
+
+        let mut path_finder: PathFinder = PathFinder {
             cols: 5,
-            rows: 4,
-            start: 6,
-            end: 18,
+            rows: 5,
+            fill_func: Some(pillar),
+            orthogonal_cost: 10,
+            diagonal_cost: 14,
             ..Default::default()
         };
+        path_finder.fill().unwrap();
+        path_finder.set_start(0, 0).unwrap();
+        path_finder.set_end(4, 4).unwrap();
 
-        const PATH_INDICES: [usize; 4] = [11, 16, 17, 18];
-        let parents = &mut path_finder.parents;
-        parents[11] = 6;
-        parents[16] = 11;
-        parents[17] = 16;
-        parents[18] = 17;
+        let mut four_connected = path_finder;
+        four_connected.find(null_mut()).unwrap();
+        assert_eq!(four_connected.has_path, 1);
+        assert_eq!(four_connected.g_score[24], 80);
 
-        PATH_INDICES
-            .iter()
-            .for_each(|&index| path_finder.state[index] = 0x2);
-        path_finder.f_score[18] = -10;
+        path_finder.movement = Movement::EightConnected;
+        assert_eq!(path_finder_heuristic(&path_finder, 0), 56);
+        assert_eq!(path_finder_heuristic(&path_finder, 4), 40);
 
-        let run = path_finder_find_step(&mut path_finder, null_mut());
-        assert_eq!(run, 0);
+        path_finder.find(null_mut()).unwrap();
         assert_eq!(path_finder.has_path, 1);
-        path_finder
-            .state
-            .iter()
-            .take((path_finder.cols * path_finder.rows).try_into().unwrap())
-            .copied()
-            .enumerate()
-            .for_each(|(index, state)| {
-                if path_finder.end == index.try_into().unwrap() {
-                    assert_eq!(state, 0x2);
-                } else if PATH_INDICES.binary_search(&index).is_ok() {
-                    assert_eq!(state, 0xa);
-                } else {
-                    assert_eq!(state, 0);
-                }
-            });
+        assert_eq!(path_finder.g_score[24], 3 * 14 + 2 * 10);
+        assert_eq!(
+            path_finder
+                .state
+                .iter()
+                .filter(|&&state| state & 0x8 != 0)
+                .count(),
+            4
+        );
     }
 
     #[test]
-    fn find_step_empty_set() {
-        let mut path_finder = PathFinder {
-            cols: 5,
-            rows: 4,
-            start: 6,
-            end: 18,
-            ..Default::default()
+    fn find_corner_cutting() {
+        /*
+         * Representation
+         *
+         * /---\   /---\
+         * |S# |   |S# |
+         * |#E |   | E |
+         * |   |   |   |
+         * \---/   \---/
+         */
+
+        let find = |walls: &[[i32; 2]], corner_cutting| {
+            let mut path_finder = PathFinder::<9>::default();
+            path_finder.initialize();
+            path_finder.cols = 3;
+            path_finder.rows = 3;
+            path_finder.movement = Movement::EightConnected;
+            path_finder.orthogonal_cost = 10;
+            path_finder.diagonal_cost = 14;
+            path_finder.corner_cutting = corner_cutting;
+            for &[col, row] in walls {
+                path_finder.state[path_finder.cell_index(col, row).unwrap()] = 0;
+            }
+            path_finder.set_start(0, 0).unwrap();
+            path_finder.set_end(1, 1).unwrap();
+            path_finder.find(null_mut()).unwrap();
+
+            if path_finder.has_path == 1 {
+                Some(path_finder.g_score[4])
+            } else {
+                None
+            }
         };
-        path_finder.f_score[18] = -10;
 
-        let run = path_finder_find_step(&mut path_finder, null_mut());
-        assert_eq!(run, 0);
-        assert_eq!(path_finder.has_path, 0);
-        assert!(path_finder.state.iter().copied().all(|state| state == 0));
+        const BOTH_SIDES: [[i32; 2]; 2] = [[1, 0], [0, 1]];
+        const ONE_SIDE: [[i32; 2]; 1] = [[1, 0]];
+        assert_eq!(find(&BOTH_SIDES, CornerCutting::Always), Some(14));
+        assert_eq!(find(&BOTH_SIDES, CornerCutting::OneSideOpen), None);
+        assert_eq!(find(&BOTH_SIDES, CornerCutting::Never), None);
+        assert_eq!(find(&ONE_SIDE, CornerCutting::Always), Some(14));
+        assert_eq!(find(&ONE_SIDE, CornerCutting::OneSideOpen), Some(14));
+        assert_eq!(find(&ONE_SIDE, CornerCutting::Never), Some(20));
     }
 
     #[test]
-    fn find_step() {
+    fn find_heuristics() {
         let mut path_finder = create_complex_map();
+        path_finder.score_func = None;
+        path_finder.movement = Movement::EightConnected;
+        path_finder.orthogonal_cost = 10;
+        path_finder.diagonal_cost = 14;
         path_finder_fill(&mut path_finder);
-        path_finder_begin(&mut path_finder);
 
-        check_next_step(&mut path_finder);
+        let find = |path_finder: &PathFinder, kind| {
+            let mut path_finder = *path_finder;
+            path_finder.heuristic = kind;
+            path_finder.find(null_mut()).unwrap();
+            assert_eq!(path_finder.has_path, 1);
+
+            let closed = path_finder.state.iter().filter(|&&state| state & 0x4 != 0);
+            (
+                path_finder.g_score[path_finder.end as usize],
+                closed.count(),
+            )
+        };
+
+        let (cost, dijkstra_closed) = find(&path_finder, HeuristicKind::Zero);
+        for &kind in &[
+            HeuristicKind::Auto,
+            HeuristicKind::Chebyshev,
+            HeuristicKind::Octile,
+            HeuristicKind::Euclidean,
+        ] {
+            let (kind_cost, closed) = find(&path_finder, kind);
+            assert_eq!(kind_cost, cost);
+            assert!(closed < dijkstra_closed);
+        }
         assert_eq!(
-            path_finder.cell(6, 5),
-            Cell {
-                state: 5,
-                ..Default::default()
-            }
+            find(&path_finder, HeuristicKind::Auto),
+            find(&path_finder, HeuristicKind::Octile)
         );
 
-        for _step in 0..312 {
-            assert!(check_next_step(&mut path_finder));
+        let mut callback = path_finder;
+        extern "C" fn zero(_path_finder: &PathFinder, _col: i32, _row: i32) -> i32 {
+            0
         }
+        callback.heuristic_func = Some(zero);
+        callback.find(null_mut()).unwrap();
+        let mut dijkstra = path_finder;
+        dijkstra.find_with(null_mut(), &Zero).unwrap();
+        assert_eq!(callback, dijkstra);
+        assert_eq!(dijkstra.g_score[dijkstra.end as usize], cost);
+
+        let mut closure = path_finder;
+        closure
+            .find_with(null_mut(), &|cell: [i32; 2], goal: [i32; 2], costs| {
+                Octile.estimate(cell, goal, costs)
+            })
+            .unwrap();
+        assert_eq!(closure.g_score[closure.end as usize], cost);
+    }
 
-        assert!(!check_next_step(&mut path_finder));
+    #[test]
+    fn find_cost() {
+        /*
+         * Representation
+         *
+         * /-----\
+         * |S333E|
+         * |     |
+         * \-----/
+         */
+
+        extern "C" fn terrain(
+            _path_finder: &mut PathFinder,
+            col: i32,
+            row: i32,
+            _data: *mut c_void,
+        ) -> i32 {
+            if row == 0 && col > 0 && col < 4 {
+                3
+            } else {
+                1
+            }
+        }
+
+        let mut path_finder: PathFinder = PathFinder {
+            cols: 5,
+            rows: 2,
+            start: 0,
+            end: 4,
+            state: [0x1; PATH_FINDER_MAX_CELLS],
+            ..Default::default()
+        };
+
+        let mut biased = path_finder;
+        biased.score_func = Some(terrain);
+        biased.find(null_mut()).unwrap();
+        assert_eq!(biased.has_path, 1);
+        assert_eq!(biased.g_score[4], 4);
+
+        path_finder.cost_func = Some(terrain);
+        path_finder.find(null_mut()).unwrap();
+        assert_eq!(path_finder.has_path, 1);
+        assert_eq!(path_finder.g_score[4], 6);
+        assert!((0..5).all(|col| path_finder.is_path(col, 0) == 0));
+
+        path_finder.clear_path();
+        path_finder.orthogonal_cost = 10;
+        path_finder.find(null_mut()).unwrap();
+        assert_eq!(path_finder.g_score[4], 60);
+
+        /* Costs below 1 count as 1 */
+        extern "C" fn negative(
+            _path_finder: &mut PathFinder,
+            _col: i32,
+            _row: i32,
+            _data: *mut c_void,
+        ) -> i32 {
+            -3
+        }
+        path_finder.clear_path();
+        path_finder.cost_func = Some(negative);
+        path_finder.find(null_mut()).unwrap();
+        assert_eq!(path_finder.has_path, 1);
+        assert_eq!(path_finder.g_score[4], 40);
+    }
+
+    #[test]
+    fn find_in_map() {
+        struct Terrain {
+            walls: Vec<[i32; 2]>,
+            swamp_cost: i32,
+            queried: usize,
+        }
+
+        impl Map for Terrain {
+            fn is_passable(&mut self, col: i32, row: i32) -> bool {
+                !self.walls.contains(&[col, row])
+            }
+
+            fn cost(&mut self, col: i32, row: i32) -> i32 {
+                self.queried += 1;
+                if row == 0 && col > 0 && col < 4 {
+                    self.swamp_cost
+                } else {
+                    1
+                }
+            }
+        }
+
+        let mut path_finder = HeapPathFinder::new(5, 3).unwrap();
+        path_finder.set_end(4, 0).unwrap();
+
+        let mut terrain = Terrain {
+            walls: vec![[2, 1]],
+            swamp_cost: 3,
+            queried: 0,
+        };
+        assert_eq!(path_finder.clone().find_in(&mut terrain), Ok(true));
+        assert!(terrain.queried > 0);
+
+        extern "C" fn wall(_path_finder: &mut HeapPathFinder, col: i32, row: i32) -> u8 {
+            ([col, row] != [2, 1]) as u8
+        }
+        extern "C" fn swamp(
+            _path_finder: &mut HeapPathFinder,
+            col: i32,
+            row: i32,
+            _data: *mut c_void,
+        ) -> i32 {
+            if row == 0 && col > 0 && col < 4 {
+                3
+            } else {
+                1
+            }
+        }
+        let mut reference = path_finder.clone();
+        reference.fill_func = Some(wall);
+        reference.cost_func = Some(swamp);
+        reference.fill().unwrap();
+        reference.find(null_mut()).unwrap();
+
+        let swamp_cost = 3;
+        let mut from_closures = path_finder.clone();
+        let found = from_closures.find_in(&mut FnMap::new(
+            |col, row| [col, row] != [2, 1],
+            |col, row| {
+                if row == 0 && col > 0 && col < 4 {
+                    swamp_cost
+                } else {
+                    1
+                }
+            },
+        ));
+        assert_eq!(found, Ok(true));
+        assert_eq!(from_closures, reference);
+        assert_eq!(from_closures.g_score[4], 8);
+
+        let mut only_passable = path_finder.clone();
+        assert_eq!(
+            only_passable.find_in(&mut |col, row| [col, row] != [2, 1]),
+            Ok(true)
+        );
+        assert_eq!(only_passable.g_score[4], 4);
     }
 
     fn check_next_step(path_finder: &mut PathFinder) -> bool {
         let current_cell_index = path_finder_lowest_in_open_set(path_finder);
         let current_cell = path_finder
             .get(current_cell_index.try_into().unwrap())
+            .unwrap()
             .to_cell();
-        let [col, row] = path_finder.col_and_row_from_index(current_cell_index.try_into().unwrap());
+        let [col, row] = path_finder
+            .col_and_row_from_index(current_cell_index.try_into().unwrap())
+            .unwrap();
         let g_score = current_cell.g_score + 1;
         let score_func = path_finder.score_func.unwrap();
 
@@ -798,7 +2890,7 @@ mod tests {
                     _ => return,
                 };
 
-                *old_cell = path_finder.cell(col, row).to_cell();
+                *old_cell = path_finder.cell(col, row).unwrap().to_cell();
             });
 
         if path_finder_find_step(path_finder, null_mut()) != 1 {
@@ -819,14 +2911,14 @@ mod tests {
                     _ => return,
                 };
 
-                let cell = path_finder.cell(col, row);
+                let cell = path_finder.cell(col, row).unwrap();
                 if *cell.parent != current_cell_index {
                     return;
                 }
 
                 if old_cell.state & 0x1 == 0 {
                     assert_eq!(
-                        path_finder.cell(col, row),
+                        path_finder.cell(col, row).unwrap(),
                         Cell {
                             state: old_cell.state | 0x4,
                             parent: old_cell.parent,
@@ -839,7 +2931,11 @@ mod tests {
                         if old_cell.state & 0x2 == 0 || g_score < old_cell.g_score {
                             let heuristics = path_finder_heuristic(
                                 path_finder,
-                                path_finder.cell_index(col, row).try_into().unwrap(),
+                                path_finder
+                                    .cell_index(col, row)
+                                    .unwrap()
+                                    .try_into()
+                                    .unwrap(),
                             );
                             let score = score_func(path_finder, col, row, null_mut());
                             let f_score = score + heuristics + g_score;
@@ -850,7 +2946,7 @@ mod tests {
                         };
 
                     assert_eq!(
-                        path_finder.cell(col, row),
+                        path_finder.cell(col, row).unwrap(),
                         Cell {
                             state: 3,
                             parent: current_cell_index,
@@ -864,177 +2960,157 @@ mod tests {
         true
     }
 
-    fn create_complex_map() -> PathFinder {
-        /*
-         * Representation
-         *              1         2
-         *    0123456789012345678901234
-         *   /-------------------------\
-         *  0|                         |
-         *  1|     fffff               |
-         *  2|     fffff               |
-         *  3|        #                |
-         *  4|        #  ffffff        |
-         *  5|      S #  ffffff        |
-         *  6|        #                |
-         *  7|##############           |
-         *  8|          11111          |
-         *  9|          f##############|
-         * 10|          f      #       |
-         *  1|              #81#  E    |
-         *  2|              #  #       |
-         *  3|              #18######1 |
-         *  4|              #          |
-         *  5|              #          |
-         *   \-------------------------/
-         */
+    /// A `cols` x `rows` grid with steps costing 10 and 14, where every cell but the `open` ones
+    /// is a wall with a chance of `walls[0]` in `walls[1]`.
+    pub(crate) fn random_grid(
+        rng: &mut SmallRng,
+        [cols, rows]: [i32; 2],
+        movement: Movement,
+        walls: [u32; 2],
+        open: &[[i32; 2]],
+    ) -> HeapPathFinder {
+        let mut path_finder = HeapPathFinder::new(cols, rows).unwrap();
+        path_finder.movement = movement;
+        path_finder.orthogonal_cost = 10;
+        path_finder.diagonal_cost = 14;
+        path_finder
+            .fill_with(|col, row| open.contains(&[col, row]) || !rng.gen_ratio(walls[0], walls[1]))
+            .unwrap();
+        path_finder
+    }
 
-        PathFinder {
-            cols: 25,
-            rows: 16,
-            start: 131,
-            end: 295,
-            fill_func: Some(create_complex_map_fill_func),
-            score_func: Some(create_complex_map_score_func),
-            ..Default::default()
+    /// Moves start and end to random passable cells, returning their indices.
+    pub(crate) fn random_endpoints<S: Storage>(
+        rng: &mut SmallRng,
+        path_finder: &mut PathFinderBase<S>,
+    ) -> [usize; 2] {
+        let [start, end] = [0, 1].map(|_| loop {
+            let index = rng.gen_range(0, path_finder.size());
+            if path_finder.state[index] & 0x1 != 0 {
+                break index;
+            }
+        });
+        path_finder.start = start as i32;
+        path_finder.end = end as i32;
+        [start, end]
+    }
+
+    /// Searches a copy of `searched` with unweighted A* over its passability and `costs`, checking
+    /// that it agrees with `found` and that the path of `searched`, if any, runs from start to
+    /// end. Returns the copy when it found a path, to compare costs against.
+    pub(crate) fn cross_check(
+        searched: &HeapPathFinder,
+        found: bool,
+        costs: &[i32],
+    ) -> Option<HeapPathFinder> {
+        let mut optimal = searched.clone();
+        optimal.clear_path();
+        optimal.heuristic_weight = 1.0;
+        let cols = optimal.cols;
+        let passable: Vec<bool> = optimal
+            .state
+            .iter()
+            .map(|&state| state & 0x1 != 0)
+            .collect();
+        let mut terrain = FnMap::new(
+            |col, row| passable[(row * cols + col) as usize],
+            |col, row| costs[(row * cols + col) as usize],
+        );
+        assert_eq!(optimal.find_in(&mut terrain), Ok(found));
+        assert_eq!(searched.has_path == 1, found);
+
+        let path = searched.path();
+        if !found {
+            assert!(path.is_empty());
+            return None;
         }
+        assert_eq!(
+            path.first(),
+            Some(&searched.position(searched.start as usize))
+        );
+        assert_eq!(path.last(), Some(&searched.position(searched.end as usize)));
+        Some(optimal)
     }
 
-    struct Pos {
-        row: i32,
-        col: i32,
+    /// The cost of the path of `path_finder` over `costs`, checking that it steps from neighbour
+    /// to passable neighbour and that the cells between its ends carry the path mark.
+    pub(crate) fn path_cost(path_finder: &HeapPathFinder, costs: &[i32]) -> i32 {
+        let path = path_finder.path();
+        let marked = path_finder.state.iter().filter(|&&state| state & 0x8 != 0);
+        assert_eq!(marked.count(), path.len().saturating_sub(2));
+        path.windows(2)
+            .map(|step| {
+                let [[col, row], [next_col, next_row]] = [step[0], step[1]];
+                let offset = [next_col - col, next_row - row];
+                let (next, step_cost) = path_finder.neighbor(col, row, offset).unwrap();
+                assert_eq!(path_finder.state[next] & 0x1, 0x1);
+                step_cost * costs[next]
+            })
+            .sum()
     }
 
-    struct Area {
-        first: Pos,
-        last: Pos,
+    /* The grid of `create_complex_map`, its digits being the dangers of its score_func */
+    const COMPLEX_MAP: &str = r"
+                1         2
+      0123456789012345678901234
+     /-------------------------\
+    0|                         |
+    1|    ffffff               |
+    2|    ffffff               |
+    3|        #                |
+    4|        #  ffffff        |
+    5|      S #  ffffff        |
+    6|        #                |
+    7|##############           |
+    8|          11111          |
+    9|          f##############|
+   10|          f      #       |
+    1|              #81#  E    |
+    2|              #  #       |
+    3|             1#18######  |
+    4|              #          |
+    5|              #          |
+     \-------------------------/
+";
+
+    fn complex_map() -> &'static AsciiMap {
+        static MAP: OnceLock<AsciiMap> = OnceLock::new();
+        MAP.get_or_init(|| COMPLEX_MAP.parse().unwrap())
     }
 
-    fn create_complex_map_fill_func(_path_finder: &mut PathFinder, col: i32, row: i32) -> u8 {
-        const WALLS: [Area; 6] = [
-            Area {
-                first: Pos { row: 3, col: 8 },
-                last: Pos { row: 6, col: 8 },
-            },
-            Area {
-                first: Pos { row: 7, col: 0 },
-                last: Pos { row: 7, col: 13 },
-            },
-            Area {
-                first: Pos { row: 9, col: 11 },
-                last: Pos { row: 9, col: 24 },
-            },
-            Area {
-                first: Pos { row: 11, col: 14 },
-                last: Pos { row: 15, col: 14 },
-            },
-            Area {
-                first: Pos { row: 10, col: 17 },
-                last: Pos { row: 13, col: 17 },
-            },
-            Area {
-                first: Pos { row: 13, col: 18 },
-                last: Pos { row: 13, col: 22 },
-            },
-        ];
+    pub(crate) fn create_complex_map() -> PathFinder {
+        let map = complex_map();
+        let [start, end] = [map.start, map.end].map(|endpoint| {
+            let [col, row] = endpoint.unwrap();
+            row * map.cols + col
+        });
+        PathFinder {
+            cols: map.cols,
+            rows: map.rows,
+            start,
+            end,
+            fill_func: Some(create_complex_map_fill_func),
+            score_func: Some(create_complex_map_score_func),
+            ..Default::default()
+        }
+    }
 
-        WALLS
-            .iter()
-            .find(|wall| {
-                wall.first.row <= row
-                    && wall.first.col <= col
-                    && wall.last.row >= row
-                    && wall.last.col >= col
-            })
-            .is_none() as u8
+    extern "C" fn create_complex_map_fill_func(
+        _path_finder: &mut PathFinder,
+        col: i32,
+        row: i32,
+    ) -> u8 {
+        let map = complex_map();
+        map.passable[(row * map.cols + col) as usize] as u8
     }
 
-    fn create_complex_map_score_func(
+    extern "C" fn create_complex_map_score_func(
         _path_finder: &mut PathFinder,
         col: i32,
         row: i32,
         _data: *mut c_void,
     ) -> i32 {
-        struct Danger {
-            area: Area,
-            score: i32,
-        }
-
-        const DANGERS: [Danger; 9] = [
-            Danger {
-                area: Area {
-                    first: Pos { row: 1, col: 4 },
-                    last: Pos { row: 2, col: 9 },
-                },
-                score: 0xf,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 4, col: 11 },
-                    last: Pos { row: 5, col: 16 },
-                },
-                score: 0xf,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 8, col: 10 },
-                    last: Pos { row: 8, col: 14 },
-                },
-                score: 1,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 9, col: 10 },
-                    last: Pos { row: 10, col: 10 },
-                },
-                score: 0xf,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 11, col: 15 },
-                    last: Pos { row: 11, col: 15 },
-                },
-                score: 8,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 11, col: 16 },
-                    last: Pos { row: 11, col: 16 },
-                },
-                score: 1,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 13, col: 15 },
-                    last: Pos { row: 13, col: 15 },
-                },
-                score: 1,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 13, col: 16 },
-                    last: Pos { row: 13, col: 16 },
-                },
-                score: 8,
-            },
-            Danger {
-                area: Area {
-                    first: Pos { row: 13, col: 13 },
-                    last: Pos { row: 13, col: 13 },
-                },
-                score: 1,
-            },
-        ];
-
-        DANGERS
-            .iter()
-            .find(|danger| {
-                danger.area.first.row <= row
-                    && danger.area.first.col <= col
-                    && danger.area.last.row >= row
-                    && danger.area.last.col >= col
-            })
-            .map(|danger| danger.score)
-            .unwrap_or(0)
+        let map = complex_map();
+        map.dangers[(row * map.cols + col) as usize]
     }
 }
@@ -0,0 +1,176 @@
+use crate::{path_finder_heuristic, Heuristic, PathFinderBase, Storage};
+use std::os::raw::c_void;
+
+/// A grid description the path finder can be filled from and searched over, capturing whatever
+/// context it needs instead of going through `data` pointers.
+pub trait Map {
+    fn is_passable(&mut self, col: i32, row: i32) -> bool;
+
+    /// The cost of stepping into the cell, scaling the orthogonal or diagonal step cost. Costs
+    /// below 1 count as 1.
+    fn cost(&mut self, _col: i32, _row: i32) -> i32 {
+        1
+    }
+
+    /// A bias added to the f_score of the cell only, as `score_func` does.
+    fn score(&mut self, _col: i32, _row: i32) -> i32 {
+        0
+    }
+}
+
+impl<F> Map for F
+where
+    F: FnMut(i32, i32) -> bool,
+{
+    fn is_passable(&mut self, col: i32, row: i32) -> bool {
+        self(col, row)
+    }
+}
+
+/// A [`Map`] made of a passability closure and a traversal cost closure.
+#[derive(Debug, Clone, Copy)]
+pub struct FnMap<P, C> {
+    pub passable: P,
+    pub cost: C,
+}
+
+impl<P, C> FnMap<P, C>
+where
+    P: FnMut(i32, i32) -> bool,
+    C: FnMut(i32, i32) -> i32,
+{
+    pub fn new(passable: P, cost: C) -> Self {
+        Self { passable, cost }
+    }
+}
+
+impl<P, C> Map for FnMap<P, C>
+where
+    P: FnMut(i32, i32) -> bool,
+    C: FnMut(i32, i32) -> i32,
+{
+    fn is_passable(&mut self, col: i32, row: i32) -> bool {
+        (self.passable)(col, row)
+    }
+
+    fn cost(&mut self, col: i32, row: i32) -> i32 {
+        (self.cost)(col, row)
+    }
+}
+
+/* What the search asks for while expanding a cell, either through the raw function pointers of
+ * the path finder or through a `Map` */
+pub(crate) trait Callbacks<S: Storage> {
+    fn cost(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32;
+    fn score(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32;
+    fn heuristic(&mut self, path_finder: &mut PathFinderBase<S>, cell: i32) -> i32;
+
+    /* The cost searches scale steps with. Lower costs would let them wander without their
+     * scores growing, so they count as 1 */
+    fn step_cost(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.cost(path_finder, col, row).max(1)
+    }
+
+    fn is_goal(&mut self, path_finder: &PathFinderBase<S>, cell: i32) -> bool {
+        cell == path_finder.end
+    }
+}
+
+pub(crate) struct RawCallbacks<'a, H: ?Sized> {
+    pub(crate) data: *mut c_void,
+    pub(crate) heuristic: Option<&'a H>,
+}
+
+impl<S: Storage, H: Heuristic + ?Sized> Callbacks<S> for RawCallbacks<'_, H> {
+    fn cost(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        match path_finder.cost_func {
+            Some(cost_func) => cost_func(path_finder, col, row, self.data),
+            None => 1,
+        }
+    }
+
+    fn score(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        match path_finder.score_func {
+            Some(score_func) => score_func(path_finder, col, row, self.data),
+            None => 0,
+        }
+    }
+
+    fn heuristic(&mut self, path_finder: &mut PathFinderBase<S>, cell: i32) -> i32 {
+        match self.heuristic {
+            Some(heuristic) => path_finder.estimate(heuristic, cell),
+            None => path_finder_heuristic(path_finder, cell),
+        }
+    }
+}
+
+pub(crate) struct MapCallbacks<'a, M: ?Sized>(pub(crate) &'a mut M);
+
+impl<S: Storage, M: Map + ?Sized> Callbacks<S> for MapCallbacks<'_, M> {
+    fn cost(&mut self, _path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.0.cost(col, row)
+    }
+
+    fn score(&mut self, _path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.0.score(col, row)
+    }
+
+    fn heuristic(&mut self, path_finder: &mut PathFinderBase<S>, cell: i32) -> i32 {
+        path_finder_heuristic(path_finder, cell)
+    }
+}
+
+/// Orders the search by g_score alone, so that every closed cell holds its exact distance.
+pub(crate) struct Flood<'a, C>(pub(crate) &'a mut C);
+
+impl<S: Storage, C: Callbacks<S>> Callbacks<S> for Flood<'_, C> {
+    fn cost(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.0.cost(path_finder, col, row)
+    }
+
+    fn score(&mut self, _path_finder: &mut PathFinderBase<S>, _col: i32, _row: i32) -> i32 {
+        0
+    }
+
+    fn heuristic(&mut self, _path_finder: &mut PathFinderBase<S>, _cell: i32) -> i32 {
+        0
+    }
+}
+
+/// Stops at the first goal cell (0x10) taken from the open set, estimating the distance to the
+/// nearest of `goals`.
+pub(crate) struct Goals<'a, C> {
+    pub(crate) callbacks: &'a mut C,
+    pub(crate) goals: Vec<[i32; 2]>,
+}
+
+impl<S: Storage, C: Callbacks<S>> Callbacks<S> for Goals<'_, C> {
+    fn cost(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.callbacks.cost(path_finder, col, row)
+    }
+
+    fn score(&mut self, path_finder: &mut PathFinderBase<S>, col: i32, row: i32) -> i32 {
+        self.callbacks.score(path_finder, col, row)
+    }
+
+    /* The heuristic of the wrapped callbacks with every goal in turn as end */
+    fn heuristic(&mut self, path_finder: &mut PathFinderBase<S>, cell: i32) -> i32 {
+        let end = path_finder.end;
+        let callbacks = &mut self.callbacks;
+        let estimate = self
+            .goals
+            .iter()
+            .map(|&[col, row]| {
+                path_finder.end = path_finder.index(col, row) as i32;
+                callbacks.heuristic(path_finder, cell)
+            })
+            .min()
+            .unwrap_or(0);
+        path_finder.end = end;
+        estimate
+    }
+
+    fn is_goal(&mut self, path_finder: &PathFinderBase<S>, cell: i32) -> bool {
+        path_finder.open_set_size > 0 && path_finder.state[cell as usize] & 0x10 != 0
+    }
+}
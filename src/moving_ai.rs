@@ -0,0 +1,220 @@
+use crate::{CornerCutting, HeapPathFinder, Map, Movement, PathFinderError};
+use std::{f64::consts::SQRT_2, ptr::null_mut, str::FromStr};
+
+/*
+ * The grid benchmarks of the Moving AI Lab, see https://movingai.com/benchmarks/formats.html.
+ * Moves are octile and never cut a corner, the step costs below approximate 1 and √2.
+ */
+
+/// The cost of an orthogonal step on a [`MovingAiMap`].
+pub const MOVING_AI_ORTHOGONAL_COST: i32 = 100_000;
+/// The cost of a diagonal step on a [`MovingAiMap`].
+pub const MOVING_AI_DIAGONAL_COST: i32 = 141_421;
+
+/// A map in the `.map` format of the Moving AI Lab benchmarks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovingAiMap {
+    pub width: i32,
+    pub height: i32,
+    /// The terrain characters row by row: `.` and `G` are ground, `S` swamp, `@` and `O` out of
+    /// bounds, `T` trees and `W` water. Only ground and swamp are passable.
+    pub terrain: Vec<u8>,
+}
+
+impl FromStr for MovingAiMap {
+    type Err = PathFinderError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().map(str::trim_end);
+        let (mut width, mut height) = (None, None);
+        loop {
+            let line = lines.next().ok_or(PathFinderError::InvalidFormat)?;
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("type"), Some("octile"), None) => {}
+                (Some("height"), Some(value), None) => height = value.parse().ok(),
+                (Some("width"), Some(value), None) => width = value.parse().ok(),
+                (Some("map"), None, None) => break,
+                _ => return Err(PathFinderError::InvalidFormat),
+            }
+        }
+
+        let (width, height): (i32, i32) =
+            width.zip(height).ok_or(PathFinderError::InvalidFormat)?;
+        if width < 1 || height < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+
+        let size = width as usize * height as usize;
+        let mut terrain = Vec::with_capacity(size);
+        for line in lines.by_ref().take(height as usize) {
+            if line.len() != width as usize || !line.bytes().all(|c| b".G@OTSW".contains(&c)) {
+                return Err(PathFinderError::InvalidFormat);
+            }
+            terrain.extend_from_slice(line.as_bytes());
+        }
+        if terrain.len() != size || lines.any(|line| !line.is_empty()) {
+            return Err(PathFinderError::InvalidFormat);
+        }
+
+        Ok(MovingAiMap {
+            width,
+            height,
+            terrain,
+        })
+    }
+}
+
+impl Map for MovingAiMap {
+    fn is_passable(&mut self, col: i32, row: i32) -> bool {
+        passable(self.terrain[(row * self.width + col) as usize])
+    }
+}
+
+fn passable(terrain: u8) -> bool {
+    matches!(terrain, b'.' | b'G' | b'S')
+}
+
+impl MovingAiMap {
+    /// A path finder the size of the map, filled from it and set up for its octile moves.
+    pub fn path_finder(&self) -> Result<HeapPathFinder, PathFinderError> {
+        let mut path_finder = HeapPathFinder::new(self.width, self.height)?;
+        path_finder.movement = Movement::EightConnected;
+        path_finder.corner_cutting = CornerCutting::Never;
+        path_finder.orthogonal_cost = MOVING_AI_ORTHOGONAL_COST;
+        path_finder.diagonal_cost = MOVING_AI_DIAGONAL_COST;
+        path_finder
+            .fill_with(|col, row| passable(self.terrain[(row * self.width + col) as usize]))?;
+        Ok(path_finder)
+    }
+
+    /// Runs the queries of `scenarios` in order on this map. A query that cannot be searched,
+    /// such as one with an endpoint on a wall, fails alone, see [`ScenarioResult::error`]. Fails
+    /// as a whole if a scenario is for a map of another size.
+    pub fn run(&self, scenarios: &[Scenario]) -> Result<Vec<ScenarioResult>, PathFinderError> {
+        let mut path_finder = self.path_finder()?;
+        scenarios
+            .iter()
+            .map(|scenario| {
+                if [scenario.width, scenario.height] != [self.width, self.height] {
+                    return Err(PathFinderError::InvalidSize);
+                }
+
+                path_finder.clear_path();
+                let search = path_finder
+                    .set_start(scenario.start[0], scenario.start[1])
+                    .and_then(|()| path_finder.set_end(scenario.goal[0], scenario.goal[1]))
+                    .and_then(|()| path_finder.find(null_mut()));
+                let found = match search {
+                    Ok(found) => found,
+                    Err(error) => {
+                        return Ok(ScenarioResult {
+                            length: None,
+                            optimal_length: scenario.optimal_length,
+                            expanded: 0,
+                            error: Some(error),
+                        })
+                    }
+                };
+
+                /* Measured on the path itself, so that the rounding of the diagonal cost does
+                 * not add up */
+                let path = path_finder.path();
+                let length = path.windows(2).map(|step| {
+                    let [[col, row], [next_col, next_row]] = [step[0], step[1]];
+                    if col != next_col && row != next_row {
+                        SQRT_2
+                    } else {
+                        1.0
+                    }
+                });
+                let expanded = path_finder.state.iter();
+                Ok(ScenarioResult {
+                    length: Some(length.sum()).filter(|_| found),
+                    optimal_length: scenario.optimal_length,
+                    expanded: expanded.filter(|&&state| state & 0x5 == 0x5).count(),
+                    error: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A query of a `.scen` file of the Moving AI Lab benchmarks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub bucket: u32,
+    /// The map file, relative to the scenario file.
+    pub map: String,
+    pub width: i32,
+    pub height: i32,
+    /// The start column and row.
+    pub start: [i32; 2],
+    /// The goal column and row.
+    pub goal: [i32; 2],
+    pub optimal_length: f64,
+}
+
+impl Scenario {
+    /// Parses the queries of a version 1 `.scen` file.
+    pub fn parse_all(text: &str) -> Result<Vec<Scenario>, PathFinderError> {
+        let mut lines = text.lines().map(str::trim_end);
+        let version = lines.next().unwrap_or_default();
+        if !version
+            .split_whitespace()
+            .eq(["version", "1"].iter().copied())
+        {
+            return Err(PathFinderError::InvalidFormat);
+        }
+
+        lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let fields: Vec<_> = line.split('\t').collect();
+                let (bucket, map, numbers) = match fields[..] {
+                    [bucket, map, ref numbers @ ..] if numbers.len() == 7 => (bucket, map, numbers),
+                    _ => return Err(PathFinderError::InvalidFormat),
+                };
+                let number = |index: usize| {
+                    numbers[index]
+                        .parse()
+                        .map_err(|_| PathFinderError::InvalidFormat)
+                };
+
+                Ok(Scenario {
+                    bucket: bucket.parse().map_err(|_| PathFinderError::InvalidFormat)?,
+                    map: map.to_owned(),
+                    width: number(0)?,
+                    height: number(1)?,
+                    start: [number(2)?, number(3)?],
+                    goal: [number(4)?, number(5)?],
+                    optimal_length: numbers[6]
+                        .parse()
+                        .map_err(|_| PathFinderError::InvalidFormat)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The outcome of running a [`Scenario`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioResult {
+    /// The length of the path found, with diagonal steps of √2, or `None` if there was none.
+    pub length: Option<f64>,
+    pub optimal_length: f64,
+    /// The number of cells the search expanded.
+    pub expanded: usize,
+    /// Why the scenario could not be searched, if it could not.
+    pub error: Option<PathFinderError>,
+}
+
+impl ScenarioResult {
+    /// Whether a path was found and its length matches the optimal one, up to the rounding of
+    /// the lengths in the scenario file and of the step costs.
+    pub fn is_optimal(&self) -> bool {
+        self.length.is_some_and(|length| {
+            (length - self.optimal_length).abs() <= 1e-4 + self.optimal_length * 1e-5
+        })
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{HeapPathFinder, Map, PathFinderBase, PathFinderError, Storage};
+use std::str::{self, FromStr};
+
+/*
+ * Netpbm greyscale (PGM, P2 and P5) and colour (PPM, P3 and P6) images, with samples of up to
+ * 16 bits scaled to 8. Grey pixels read as three equal channels.
+ */
+
+/// An image read from or written to a PGM or PPM file, one RGB pixel per cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pnm {
+    pub width: i32,
+    pub height: i32,
+    /// The pixels row by row.
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Pnm {
+    /// An image of `width` x `height` pixels of `colour`.
+    pub fn new(width: i32, height: i32, colour: [u8; 3]) -> Result<Self, PathFinderError> {
+        if width < 1 || height < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+        let size = width
+            .checked_mul(height)
+            .ok_or(PathFinderError::GridTooLarge)?;
+
+        Ok(Pnm {
+            width,
+            height,
+            pixels: vec![colour; size as usize],
+        })
+    }
+
+    /// Reads a PGM or PPM file, in its plain or binary form.
+    pub fn parse(bytes: &[u8]) -> Result<Self, PathFinderError> {
+        let mut reader = Reader { bytes, position: 0 };
+        let (channels, binary) = match reader.token()? {
+            b"P2" => (1, false),
+            b"P3" => (3, false),
+            b"P5" => (1, true),
+            b"P6" => (3, true),
+            _ => return Err(PathFinderError::InvalidFormat),
+        };
+        let width: i32 = reader.number()?;
+        let height: i32 = reader.number()?;
+        let max: u32 = reader.number()?;
+        if width < 1 || height < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+        if max < 1 || max > u16::MAX.into() {
+            return Err(PathFinderError::InvalidFormat);
+        }
+        let size = width
+            .checked_mul(height)
+            .ok_or(PathFinderError::GridTooLarge)? as usize;
+
+        /* A single whitespace character separates the header from binary samples */
+        if binary {
+            reader.position += 1;
+        }
+        let mut sample = || {
+            let value = if !binary {
+                reader.number()?
+            } else if max < 256 {
+                u32::from(reader.take(1)?[0])
+            } else {
+                let bytes = reader.take(2)?;
+                u32::from(u16::from_be_bytes([bytes[0], bytes[1]]))
+            };
+            if value > max {
+                return Err(PathFinderError::InvalidFormat);
+            }
+            Ok(((value * 255 + max / 2) / max) as u8)
+        };
+
+        let mut pixels = Vec::new();
+        for _ in 0..size {
+            pixels.push(if channels == 1 {
+                let grey = sample()?;
+                [grey; 3]
+            } else {
+                [sample()?, sample()?, sample()?]
+            });
+        }
+
+        Ok(Pnm {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Writes the image as a binary PPM file.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.pixels.iter().flatten());
+        bytes
+    }
+
+    /// Paints the search state of `path_finder` over the image, which must be of the size of
+    /// the grid. Open and closed cells are tinted, path cells, start and end painted over.
+    pub fn draw_search<S: Storage>(
+        &mut self,
+        path_finder: &PathFinderBase<S>,
+        colours: &SearchColours,
+    ) -> Result<(), PathFinderError> {
+        if [self.width, self.height] != [path_finder.cols, path_finder.rows] {
+            return Err(PathFinderError::InvalidSize);
+        }
+
+        let tint = |pixel: [u8; 3], colour: [u8; 3]| {
+            let mut tinted = pixel;
+            for (channel, tint) in tinted.iter_mut().zip(&colour) {
+                *channel = ((u16::from(*channel) + u16::from(*tint)) / 2) as u8;
+            }
+            tinted
+        };
+        for (index, pixel) in self.pixels.iter_mut().enumerate() {
+            let state = path_finder.state[index];
+            *pixel = if index as i32 == path_finder.start {
+                colours.start
+            } else if index as i32 == path_finder.end {
+                colours.end
+            } else if state & 0x8 != 0 {
+                colours.path
+            } else if state & 0x4 != 0 && state & 0x1 != 0 {
+                tint(*pixel, colours.closed)
+            } else if state & 0x2 != 0 {
+                tint(*pixel, colours.open)
+            } else {
+                *pixel
+            };
+        }
+        Ok(())
+    }
+}
+
+/// The colours [`Pnm::draw_search`] paints with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchColours {
+    pub start: [u8; 3],
+    pub end: [u8; 3],
+    pub path: [u8; 3],
+    pub closed: [u8; 3],
+    pub open: [u8; 3],
+}
+
+impl Default for SearchColours {
+    fn default() -> Self {
+        SearchColours {
+            start: [0, 192, 0],
+            end: [192, 0, 0],
+            path: [255, 0, 0],
+            closed: [0, 0, 255],
+            open: [0, 255, 255],
+        }
+    }
+}
+
+/// A grid read from an image, each pixel turned into a cell through a palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageMap {
+    pub cols: i32,
+    pub rows: i32,
+    /// The cost of every cell row by row, `None` for the impassable ones.
+    pub costs: Vec<Option<i32>>,
+}
+
+impl ImageMap {
+    /// Maps every pixel of `image` through `palette`, which returns the cost of the cell of that
+    /// colour or `None` if it is impassable.
+    pub fn new<P: FnMut([u8; 3]) -> Option<i32>>(image: &Pnm, palette: P) -> Self {
+        ImageMap {
+            cols: image.width,
+            rows: image.height,
+            costs: image.pixels.iter().copied().map(palette).collect(),
+        }
+    }
+
+    /// A path finder the size of the map and filled from it. Costs only apply when searching
+    /// with the map, as in [`find_in`](PathFinderBase::find_in).
+    pub fn path_finder(&self) -> Result<HeapPathFinder, PathFinderError> {
+        let mut path_finder = HeapPathFinder::new(self.cols, self.rows)?;
+        path_finder.fill_with(|col, row| self.costs[(row * self.cols + col) as usize].is_some())?;
+        Ok(path_finder)
+    }
+}
+
+impl Map for ImageMap {
+    fn is_passable(&mut self, col: i32, row: i32) -> bool {
+        self.costs[(row * self.cols + col) as usize].is_some()
+    }
+
+    fn cost(&mut self, col: i32, row: i32) -> i32 {
+        self.costs[(row * self.cols + col) as usize].unwrap_or(1)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /* The next run of non-whitespace bytes, skipping comments */
+    fn token(&mut self) -> Result<&'a [u8], PathFinderError> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while !matches!(self.bytes.get(self.position), None | Some(b'\n')) {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(PathFinderError::InvalidFormat),
+            }
+        }
+
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#')
+        {
+            self.position += 1;
+        }
+        Ok(&self.bytes[start..self.position])
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, PathFinderError> {
+        let token = self.token()?;
+        str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or(PathFinderError::InvalidFormat)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PathFinderError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(PathFinderError::InvalidFormat)?;
+        self.position += len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn pnm() {
+        /* Black walls, white ground and grey mud costing 5 */
+        let plain = b"P2
+# A corridor around a wall
+5 3
+15
+15 15 15 15 15
+0 0 0 7 15
+15 15 15 15 15
+";
+        let image = Pnm::parse(plain).unwrap();
+        assert_eq!([image.width, image.height], [5, 3]);
+        assert_eq!(image.pixels[..4], [[255; 3], [255; 3], [255; 3], [255; 3]]);
+        assert_eq!(image.pixels[8], [119; 3]);
+
+        let binary = [
+            &b"P5 5 3 255\n"[..],
+            &image
+                .pixels
+                .iter()
+                .map(|pixel| pixel[0])
+                .collect::<Vec<_>>(),
+        ]
+        .concat();
+        assert_eq!(Pnm::parse(&binary), Ok(image.clone()));
+        let wide: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|pixel| [pixel[0], pixel[0], 0, 0, 0, 0])
+            .collect();
+        let wide = Pnm::parse(&[&b"P6\n5 3\n65535\n"[..], &wide].concat()).unwrap();
+        assert!(wide
+            .pixels
+            .iter()
+            .zip(&image.pixels)
+            .all(|(wide, pixel)| wide[0] == pixel[0] && wide[1] == 0));
+        let colour = Pnm::parse(b"P3 2 1 255 1 2 3 4 5 6").unwrap();
+        assert_eq!(colour.pixels, [[1, 2, 3], [4, 5, 6]]);
+
+        let mut map = ImageMap::new(&image, |[grey, _, _]| match grey {
+            0 => None,
+            255 => Some(1),
+            _ => Some(5),
+        });
+        assert_eq!(map.costs[5..10], [None, None, None, Some(5), Some(1)]);
+        let mut path_finder = map.path_finder().unwrap();
+        path_finder.set_end(0, 2).unwrap();
+        assert_eq!(path_finder.find_in(&mut map), Ok(true));
+        assert_eq!(path_finder.g_score[10], 10);
+
+        let mut drawn = image.clone();
+        drawn
+            .draw_search(&path_finder, &SearchColours::default())
+            .unwrap();
+        let drawn = Pnm::parse(&drawn.to_ppm()).unwrap();
+        assert_eq!(drawn.pixels[0], SearchColours::default().start);
+        assert_eq!(drawn.pixels[10], SearchColours::default().end);
+        assert_eq!(drawn.pixels[4], SearchColours::default().path);
+        assert_eq!(drawn.pixels[5], [0; 3]);
+        assert_eq!(drawn.pixels[8], [59, 187, 187]);
+
+        let small = Pnm::new(2, 2, [0; 3]).unwrap();
+        assert_eq!(
+            small
+                .clone()
+                .draw_search(&path_finder, &SearchColours::default()),
+            Err(PathFinderError::InvalidSize)
+        );
+        assert_eq!(
+            Pnm::parse(b"P5 2 2 255\n\0\0\0"),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(
+            Pnm::parse(b"P2 1 1 15 16"),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(
+            Pnm::parse(b"P4 1 1 1 0"),
+            Err(PathFinderError::InvalidFormat)
+        );
+        assert_eq!(Pnm::parse(b"P2 0 1 15"), Err(PathFinderError::InvalidSize));
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{
+    path_finder_open_set_push, CornerCutting, HeuristicKind, Movement, PathFinderBase,
+    PathFinderError, Storage,
+};
+use std::{
+    convert::TryInto,
+    fmt::Write as _,
+    str::{FromStr, Lines},
+};
+
+/*
+ * Saves of the grid, the search settings and the per-cell arrays, in two forms holding the same
+ * fields in the same order. The text form is `uastar <version>`, then a `<name> <value>` line per
+ * field, then `cells` and a `<state> <parent> <g_score> <f_score>` line per cell. The binary
+ * form is the `uastar` magic, then the version and every field in little endian, the cells
+ * taking 13 bytes each. The open set is not saved but rebuilt from the open cells, and function
+ * pointers and `data` are left to the path finder loading the save.
+ */
+
+/// The version written by [`save_text`](PathFinderBase::save_text) and
+/// [`save_binary`](PathFinderBase::save_binary), the only one they load.
+pub const PATH_FINDER_SAVE_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 6] = b"uastar";
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Saves the grid and the search state as text.
+    pub fn save_text(&self) -> String {
+        let mut writer = TextWriter(format!("uastar {}\n", PATH_FINDER_SAVE_VERSION));
+        self.save(&mut writer);
+        writer.0
+    }
+
+    /// Saves the grid and the search state in the compact binary form.
+    pub fn save_binary(&self) -> Vec<u8> {
+        let mut writer = BinaryWriter(MAGIC.to_vec());
+        writer
+            .0
+            .extend_from_slice(&PATH_FINDER_SAVE_VERSION.to_le_bytes());
+        self.save(&mut writer);
+        writer.0
+    }
+
+    /// Loads a save of [`save_text`](Self::save_text), keeping the function pointers and `data`
+    /// of this path finder. A search saved while running resumes with
+    /// [`find_step`](Self::find_step). Nothing changes if the save is invalid.
+    pub fn load_text(&mut self, text: &str) -> Result<(), PathFinderError> {
+        let mut lines = text.lines();
+        let version = lines.next().and_then(|line| line.strip_prefix("uastar "));
+        if version != Some(&PATH_FINDER_SAVE_VERSION.to_string()) {
+            return Err(PathFinderError::InvalidFormat);
+        }
+
+        self.load(TextReader(lines))
+    }
+
+    /// Loads a save of [`save_binary`](Self::save_binary), like [`load_text`](Self::load_text).
+    pub fn load_binary(&mut self, bytes: &[u8]) -> Result<(), PathFinderError> {
+        let mut reader = BinaryReader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC
+            || reader.take(4)? != PATH_FINDER_SAVE_VERSION.to_le_bytes()
+        {
+            return Err(PathFinderError::InvalidFormat);
+        }
+
+        self.load(reader)
+    }
+
+    fn save<W: Writer>(&self, writer: &mut W) {
+        writer.int("cols", self.cols);
+        writer.int("rows", self.rows);
+        writer.int("start", self.start);
+        writer.int("end", self.end);
+        writer.byte("has_path", self.has_path);
+        writer.float("path_weight", self.path_weight);
+        writer.byte("movement", self.movement as u8);
+        writer.int("orthogonal_cost", self.orthogonal_cost);
+        writer.int("diagonal_cost", self.diagonal_cost);
+        writer.byte("corner_cutting", self.corner_cutting as u8);
+        writer.byte("heuristic", self.heuristic as u8);
+        writer.float("heuristic_weight", self.heuristic_weight);
+
+        writer.begin_cells();
+        for index in 0..self.size() {
+            writer.cell([
+                self.state[index].into(),
+                self.parents[index],
+                self.g_score[index],
+                self.f_score[index],
+            ]);
+        }
+    }
+
+    fn load<R: Reader>(&mut self, mut reader: R) -> Result<(), PathFinderError> {
+        let invalid = PathFinderError::InvalidFormat;
+        let cols = reader.int("cols")?;
+        let rows = reader.int("rows")?;
+        if cols < 1 || rows < 1 {
+            return Err(PathFinderError::InvalidSize);
+        }
+        let size = cols
+            .checked_mul(rows)
+            .ok_or(PathFinderError::GridTooLarge)?;
+
+        let start = reader.int("start")?;
+        let end = reader.int("end")?;
+        let has_path = reader.byte("has_path")?;
+        let path_weight = reader.float("path_weight")?;
+        let movement = match reader.byte("movement")? {
+            0 => Movement::FourConnected,
+            1 => Movement::EightConnected,
+            _ => return Err(invalid),
+        };
+        let orthogonal_cost = reader.int("orthogonal_cost")?;
+        let diagonal_cost = reader.int("diagonal_cost")?;
+        let corner_cutting = match reader.byte("corner_cutting")? {
+            0 => CornerCutting::Always,
+            1 => CornerCutting::OneSideOpen,
+            2 => CornerCutting::Never,
+            _ => return Err(invalid),
+        };
+        let heuristic = match reader.byte("heuristic")? {
+            0 => HeuristicKind::Auto,
+            1 => HeuristicKind::Manhattan,
+            2 => HeuristicKind::Chebyshev,
+            3 => HeuristicKind::Octile,
+            4 => HeuristicKind::Euclidean,
+            5 => HeuristicKind::Zero,
+            _ => return Err(invalid),
+        };
+        let heuristic_weight = reader.float("heuristic_weight")?;
+        if start < 0 || start >= size || end < 0 || end >= size || has_path > 1 {
+            return Err(invalid);
+        }
+
+        /* Read before allocating, so that a bogus size fails on the missing cells */
+        reader.begin_cells()?;
+        let mut cells = Vec::new();
+        for _ in 0..size {
+            let cell = reader.cell()?;
+            if cell[0] > u8::MAX.into() || cell[1] < 0 || cell[1] >= size {
+                return Err(invalid);
+            }
+            cells.push(cell);
+        }
+        reader.finish()?;
+
+        let size = size as usize;
+        let mut state = S::state(size);
+        if state.as_ref().len() < size {
+            return Err(PathFinderError::GridTooLarge);
+        }
+        let mut parents = S::scores(size);
+        let mut g_score = S::scores(size);
+        let mut f_score = S::scores(size);
+        for (index, [cell_state, parent, g, f]) in cells.into_iter().enumerate() {
+            state[index] = cell_state as u8;
+            parents[index] = parent;
+            g_score[index] = g;
+            f_score[index] = f;
+        }
+
+        self.cols = cols;
+        self.rows = rows;
+        self.start = start;
+        self.end = end;
+        self.has_path = has_path;
+        self.path_weight = path_weight;
+        self.state = state;
+        self.parents = parents;
+        self.g_score = g_score;
+        self.f_score = f_score;
+        self.open_set = S::scores(size);
+        self.open_set_index = S::scores(size);
+        self.open_set_size = 0;
+        self.movement = movement;
+        self.orthogonal_cost = orthogonal_cost;
+        self.diagonal_cost = diagonal_cost;
+        self.corner_cutting = corner_cutting;
+        self.heuristic = heuristic;
+        self.heuristic_weight = heuristic_weight;
+
+        /* The heap breaks ties on the cell index, so its order only depends on the f_scores */
+        for index in 0..size {
+            if self.state[index] & 0x2 != 0 {
+                self.state[index] &= !0x2;
+                path_finder_open_set_push(self, index as i32);
+            }
+        }
+        Ok(())
+    }
+}
+
+trait Writer {
+    fn int(&mut self, name: &str, value: i32);
+    fn byte(&mut self, name: &str, value: u8);
+    fn float(&mut self, name: &str, value: f32);
+    fn begin_cells(&mut self);
+    fn cell(&mut self, cell: [i32; 4]);
+}
+
+trait Reader {
+    fn int(&mut self, name: &str) -> Result<i32, PathFinderError>;
+    fn byte(&mut self, name: &str) -> Result<u8, PathFinderError>;
+    fn float(&mut self, name: &str) -> Result<f32, PathFinderError>;
+    fn begin_cells(&mut self) -> Result<(), PathFinderError>;
+    fn cell(&mut self) -> Result<[i32; 4], PathFinderError>;
+    fn finish(self) -> Result<(), PathFinderError>;
+}
+
+struct TextWriter(String);
+
+impl Writer for TextWriter {
+    fn int(&mut self, name: &str, value: i32) {
+        writeln!(self.0, "{} {}", name, value).unwrap();
+    }
+
+    fn byte(&mut self, name: &str, value: u8) {
+        writeln!(self.0, "{} {}", name, value).unwrap();
+    }
+
+    fn float(&mut self, name: &str, value: f32) {
+        writeln!(self.0, "{} {}", name, value).unwrap();
+    }
+
+    fn begin_cells(&mut self) {
+        self.0.push_str("cells\n");
+    }
+
+    fn cell(&mut self, [state, parent, g_score, f_score]: [i32; 4]) {
+        writeln!(self.0, "{} {} {} {}", state, parent, g_score, f_score).unwrap();
+    }
+}
+
+struct TextReader<'a>(Lines<'a>);
+
+impl TextReader<'_> {
+    fn field<T: FromStr>(&mut self, name: &str) -> Result<T, PathFinderError> {
+        let line = self.0.next().ok_or(PathFinderError::InvalidFormat)?;
+        match line.split_once(' ') {
+            Some((field, value)) if field == name => {
+                value.parse().map_err(|_| PathFinderError::InvalidFormat)
+            }
+            _ => Err(PathFinderError::InvalidFormat),
+        }
+    }
+}
+
+impl Reader for TextReader<'_> {
+    fn int(&mut self, name: &str) -> Result<i32, PathFinderError> {
+        self.field(name)
+    }
+
+    fn byte(&mut self, name: &str) -> Result<u8, PathFinderError> {
+        self.field(name)
+    }
+
+    fn float(&mut self, name: &str) -> Result<f32, PathFinderError> {
+        self.field(name)
+    }
+
+    fn begin_cells(&mut self) -> Result<(), PathFinderError> {
+        if self.0.next() != Some("cells") {
+            return Err(PathFinderError::InvalidFormat);
+        }
+        Ok(())
+    }
+
+    fn cell(&mut self) -> Result<[i32; 4], PathFinderError> {
+        let line = self.0.next().ok_or(PathFinderError::InvalidFormat)?;
+        let values: Vec<i32> = line
+            .split(' ')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| PathFinderError::InvalidFormat)?;
+        values[..]
+            .try_into()
+            .map_err(|_| PathFinderError::InvalidFormat)
+    }
+
+    fn finish(mut self) -> Result<(), PathFinderError> {
+        if !self.0.all(str::is_empty) {
+            return Err(PathFinderError::InvalidFormat);
+        }
+        Ok(())
+    }
+}
+
+struct BinaryWriter(Vec<u8>);
+
+impl Writer for BinaryWriter {
+    fn int(&mut self, _name: &str, value: i32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn byte(&mut self, _name: &str, value: u8) {
+        self.0.push(value);
+    }
+
+    fn float(&mut self, _name: &str, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn begin_cells(&mut self) {}
+
+    fn cell(&mut self, [state, parent, g_score, f_score]: [i32; 4]) {
+        self.0.push(state as u8);
+        for value in [parent, g_score, f_score].iter() {
+            self.0.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+struct BinaryReader<'a>(&'a [u8]);
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PathFinderError> {
+        if self.0.len() < len {
+            return Err(PathFinderError::InvalidFormat);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn word(&mut self) -> Result<[u8; 4], PathFinderError> {
+        Ok(self.take(4)?.try_into().unwrap())
+    }
+}
+
+impl Reader for BinaryReader<'_> {
+    fn int(&mut self, _name: &str) -> Result<i32, PathFinderError> {
+        Ok(i32::from_le_bytes(self.word()?))
+    }
+
+    fn byte(&mut self, _name: &str) -> Result<u8, PathFinderError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn float(&mut self, _name: &str) -> Result<f32, PathFinderError> {
+        Ok(f32::from_le_bytes(self.word()?))
+    }
+
+    fn begin_cells(&mut self) -> Result<(), PathFinderError> {
+        Ok(())
+    }
+
+    fn cell(&mut self) -> Result<[i32; 4], PathFinderError> {
+        let state = self.byte("state")?;
+        Ok([
+            state.into(),
+            self.int("parent")?,
+            self.int("g_score")?,
+            self.int("f_score")?,
+        ])
+    }
+
+    fn finish(self) -> Result<(), PathFinderError> {
+        if !self.0.is_empty() {
+            return Err(PathFinderError::InvalidFormat);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    #[test]
+    fn save() {
+        let mut rng = SmallRng::seed_from_u64(23);
+        let mut path_finder = HeapPathFinder::new(40, 25).unwrap();
+        path_finder.movement = Movement::EightConnected;
+        path_finder.orthogonal_cost = 10;
+        path_finder.diagonal_cost = 14;
+        path_finder.corner_cutting = CornerCutting::OneSideOpen;
+        path_finder.heuristic_weight = 1.5;
+        path_finder
+            .fill_with(|col, row| [[0, 0], [39, 24]].contains(&[col, row]) || !rng.gen_ratio(1, 4))
+            .unwrap();
+        path_finder.set_end(39, 24).unwrap();
+        extern "C" fn bias<S: Storage>(
+            _path_finder: &mut PathFinderBase<S>,
+            col: i32,
+            row: i32,
+            _data: *mut c_void,
+        ) -> i32 {
+            (col * row) % 3
+        }
+        path_finder.score_func = Some(bias);
+
+        let mut finished = path_finder.clone();
+        assert!(finished.find(null_mut()).unwrap());
+
+        path_finder.begin();
+        for _step in 0..20 {
+            assert_eq!(path_finder.find_step(null_mut()), 1);
+        }
+        let text = path_finder.save_text();
+        let binary = path_finder.save_binary();
+        assert!(text.starts_with("uastar 1\ncols 40\nrows 25\n"));
+        assert_eq!(binary.len(), 6 + 4 + 6 * 4 + 2 * 4 + 4 + 40 * 25 * 13);
+
+        /* Resuming from either save, with the callbacks of the loading path finder */
+        let mut resumed = HeapPathFinder::new(1, 1).unwrap();
+        resumed.score_func = path_finder.score_func;
+        resumed.load_text(&text).unwrap();
+        assert_eq!(resumed.save_binary(), binary);
+        assert_eq!(resumed.open_set_size, path_finder.open_set_size);
+        while resumed.find_step(null_mut()) == 1 {}
+        assert_eq!(resumed, finished);
+        assert_eq!(resumed.path(), finished.path());
+
+        let mut resumed: PathFinder = PathFinder {
+            score_func: Some(bias),
+            ..Default::default()
+        };
+        let len = unsafe { path_finder_save(&resumed, null_mut(), 0) };
+        assert_eq!(len, resumed.save_binary().len());
+        assert_eq!(
+            unsafe { path_finder_load(&mut resumed, binary.as_ptr(), binary.len()) },
+            PATH_FINDER_OK
+        );
+        while path_finder_find_step(&mut resumed, null_mut()) == 1 {}
+        assert_eq!(resumed.path(), finished.path());
+        assert_eq!(resumed.g_score[999], finished.g_score[999]);
+        let mut buffer = vec![0; binary.len()];
+        let len = unsafe { path_finder_save(&resumed, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(buffer, resumed.save_binary()[..len]);
+
+        /* Invalid saves leave the path finder as it was */
+        let mut small: PathFinder<64> = PathFinder {
+            cols: 8,
+            rows: 8,
+            ..Default::default()
+        };
+        let before = small.save_binary();
+        assert_eq!(
+            small.load_binary(&binary),
+            Err(PathFinderError::GridTooLarge)
+        );
+        let errors = [
+            small.load_binary(&binary[..binary.len() - 1]),
+            small.load_binary(&[binary.as_slice(), &[0]].concat()),
+            small.load_binary(&[&b"uastar\x02\0\0\0"[..], &binary[10..]].concat()),
+            small.load_text(&text.replace("uastar 1", "uastar 2")),
+            small.load_text(&text.replace("movement 1", "movement 2")),
+            small.load_text(&text.replace("cols 40", "cols forty")),
+            small.load_text(&text[..text.len() - 20]),
+            small.load_text(&text.replace("\ncells\n", "\n")),
+        ];
+        assert!(errors
+            .iter()
+            .all(|error| *error == Err(PathFinderError::InvalidFormat)));
+        assert_eq!(
+            small.load_text(&text.replace("rows 25", "rows 0")),
+            Err(PathFinderError::InvalidSize)
+        );
+        assert_eq!(small.save_binary(), before);
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    fmt::Debug,
+    ops::{Index, IndexMut},
+};
+
+/// Backing memory for the per-cell arrays of a [`PathFinderBase`](crate::PathFinderBase).
+pub trait Storage {
+    type State: AsRef<[u8]>
+        + AsMut<[u8]>
+        + Index<usize, Output = u8>
+        + IndexMut<usize>
+        + Clone
+        + Debug;
+    type Scores: AsRef<[i32]>
+        + AsMut<[i32]>
+        + Index<usize, Output = i32>
+        + IndexMut<usize>
+        + Clone
+        + Debug;
+
+    /// Creates the zeroed state array for a grid of `cells` cells.
+    fn state(cells: usize) -> Self::State;
+
+    /// Creates a zeroed score array for a grid of `cells` cells.
+    fn scores(cells: usize) -> Self::Scores;
+}
+
+/// Inline storage for at most `N` cells, the layout exposed through the C ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayStorage<const N: usize>;
+
+impl<const N: usize> Storage for ArrayStorage<N> {
+    type State = [u8; N];
+    type Scores = [i32; N];
+
+    fn state(_cells: usize) -> Self::State {
+        [0; N]
+    }
+
+    fn scores(_cells: usize) -> Self::Scores {
+        [0; N]
+    }
+}
+
+/// Heap storage sized from the number of cells of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStorage;
+
+impl Storage for HeapStorage {
+    type State = Vec<u8>;
+    type Scores = Vec<i32>;
+
+    fn state(cells: usize) -> Self::State {
+        vec![0; cells]
+    }
+
+    fn scores(cells: usize) -> Self::Scores {
+        vec![0; cells]
+    }
+}
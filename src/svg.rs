@@ -0,0 +1,255 @@
+use crate::{
+    map::{Callbacks, MapCallbacks, RawCallbacks},
+    Heuristic, Map, PathFinderBase, Storage,
+};
+use std::{fmt::Write as _, os::raw::c_void};
+
+/// What [`to_svg`](PathFinderBase::to_svg) draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// The side of a cell, in SVG user units.
+    pub cell_size: u32,
+    /// Whether every open or closed cell is drawn with an arrow to its parent.
+    pub parents: bool,
+    /// Whether every open or closed cell is annotated with its g_score, top left, and its
+    /// f_score, bottom right.
+    pub scores: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 24,
+            parents: true,
+            scores: false,
+        }
+    }
+}
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Draws the grid and the state of the search as an SVG document: walls, the scores of
+    /// `score_func` as a heat map from white to red, open and closed cells, the path once found,
+    /// start and end. Works at any point of a search, as only open and closed cells are known to
+    /// have a parent and scores.
+    pub fn to_svg(&mut self, data: *mut c_void, options: &SvgOptions) -> String {
+        self.svg_by(
+            &mut RawCallbacks::<dyn Heuristic> {
+                data,
+                heuristic: None,
+            },
+            options,
+        )
+    }
+
+    /// Like [`to_svg`](Self::to_svg), taking the scores from `map`.
+    pub fn to_svg_in<M: Map + ?Sized>(&mut self, map: &mut M, options: &SvgOptions) -> String {
+        self.svg_by(&mut MapCallbacks(map), options)
+    }
+
+    fn svg_by<C: Callbacks<S>>(&mut self, callbacks: &mut C, options: &SvgOptions) -> String {
+        let size = options.cell_size.max(1) as i32;
+        let (width, height) = (self.cols.max(0) * size, self.rows.max(0) * size);
+        let centre = |[col, row]: [i32; 2]| [col * size + size / 2, row * size + size / 2];
+
+        let mut scores = Vec::with_capacity(self.size());
+        for index in 0..self.size() {
+            let [col, row] = self.position(index);
+            let passable = self.state[index] & 0x1 != 0;
+            scores.push(Some(callbacks.score(self, col, row)).filter(|_| passable));
+        }
+        let lowest = scores.iter().flatten().copied().min().unwrap_or(0);
+        let highest = scores.iter().flatten().copied().max().unwrap_or(0);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"{f}\">",
+            w = width,
+            h = height,
+            f = (size / 4).max(1),
+        );
+        svg.push_str(
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 6 6\" refX=\"6\" refY=\"3\" \
+             markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\">\
+             <path d=\"M0,0 L6,3 L0,6 z\" fill=\"#555\"/></marker></defs>\n",
+        );
+
+        /* Walls and the heat map, then the open and closed sets over them */
+        for (index, score) in scores.iter().enumerate() {
+            let [col, row] = self.position(index);
+            let fill = match *score {
+                None => "#333".to_owned(),
+                Some(score) if highest > lowest => {
+                    let heat = f64::from(score - lowest) / f64::from(highest - lowest);
+                    let fade = |channel: f64| (255.0 - channel * heat).round() as u8;
+                    format!("#ff{:02x}{:02x}", fade(155.0), fade(255.0))
+                }
+                Some(_) => "#fff".to_owned(),
+            };
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\" \
+                 stroke=\"#ccc\"/>",
+                col * size,
+                row * size,
+                fill,
+                s = size,
+            );
+        }
+        for index in 0..self.size() {
+            let state = self.state[index];
+            let colour = match state {
+                state if state & 0x1 == 0 => continue,
+                state if state & 0x4 != 0 => "#1565c0",
+                state if state & 0x2 != 0 => "#4fc3f7",
+                _ => continue,
+            };
+            let [col, row] = self.position(index);
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\" \
+                 fill-opacity=\"0.3\"/>",
+                col * size,
+                row * size,
+                colour,
+                s = size,
+            );
+        }
+
+        for index in 0..self.size() {
+            let state = self.state[index];
+            if state & 0x1 == 0 || state & 0x6 == 0 {
+                continue;
+            }
+            let [x, y] = centre(self.position(index));
+            let parent = self.parents[index];
+            if options.parents && index as i32 != self.start && parent != index as i32 {
+                let [to_x, to_y] = centre(self.position(parent as usize));
+                /* Stop the arrows short of the centre of the parent */
+                let (end_x, end_y) = (x + (to_x - x) * 2 / 3, y + (to_y - y) * 2 / 3);
+                let _ = writeln!(
+                    svg,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#555\" \
+                     marker-end=\"url(#arrow)\"/>",
+                    x, y, end_x, end_y,
+                );
+            }
+            if options.scores {
+                let [col, row] = self.position(index);
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\">{}</text>\
+                     <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+                    col * size + 1,
+                    row * size + size / 4,
+                    self.g_score[index],
+                    (col + 1) * size - 1,
+                    (row + 1) * size - 1,
+                    self.f_score[index],
+                );
+            }
+        }
+
+        let path = self.path();
+        if !path.is_empty() {
+            let points: Vec<_> = path
+                .iter()
+                .map(|&cell| {
+                    let [x, y] = centre(cell);
+                    format!("{},{}", x, y)
+                })
+                .collect();
+            let _ = writeln!(
+                svg,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"#e53935\" stroke-width=\"{}\" \
+                 stroke-linejoin=\"round\"/>",
+                points.join(" "),
+                (size / 6).max(1),
+            );
+        }
+
+        for (index, colour) in [(self.start, "#43a047"), (self.end, "#e53935")].iter() {
+            if *index < 0 || *index as usize >= self.size() {
+                continue;
+            }
+            let [x, y] = centre(self.position(*index as usize));
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                x,
+                y,
+                (size / 3).max(1),
+                colour,
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn svg() {
+        let map: AsciiMap = "
+S..#....
+...#....
+...#...E
+........
+"
+        .parse()
+        .unwrap();
+        let mut path_finder = map.path_finder().unwrap();
+        extern "C" fn bias(
+            _path_finder: &mut HeapPathFinder,
+            col: i32,
+            _row: i32,
+            _data: *mut c_void,
+        ) -> i32 {
+            col
+        }
+        path_finder.score_func = Some(bias);
+        path_finder.begin();
+        for _ in 0..3 {
+            assert_eq!(path_finder.find_step(null_mut()), 1);
+        }
+
+        let options = SvgOptions {
+            cell_size: 10,
+            ..SvgOptions::default()
+        };
+        let drawn = path_finder.to_svg(null_mut(), &options);
+        assert!(drawn.starts_with("<svg "));
+        assert!(drawn.contains("width=\"80\" height=\"40\""));
+        assert!(drawn.ends_with("</svg>\n"));
+        assert_eq!(drawn.matches("fill=\"#333\"").count(), 3);
+        assert!(drawn.contains("x=\"70\" y=\"0\" width=\"10\" height=\"10\" fill=\"#ff6400\""));
+        let visited = path_finder
+            .state
+            .iter()
+            .filter(|&&state| state & 0x7 > 0x1)
+            .count();
+        assert_eq!(drawn.matches("<line ").count(), visited - 1);
+        assert!(!drawn.contains("<polyline"));
+        assert!(!drawn.contains("<text"));
+
+        while path_finder.find_step(null_mut()) == 1 {}
+        assert_eq!(path_finder.has_path, 1);
+        let options = SvgOptions {
+            parents: false,
+            scores: true,
+            ..options
+        };
+        let drawn = path_finder.to_svg(null_mut(), &options);
+        assert_eq!(drawn.matches("<polyline").count(), 1);
+        assert!(drawn.contains("points=\"5,5 "));
+        assert!(!drawn.contains("<line "));
+        let end = path_finder.end as usize;
+        assert!(drawn.contains(&format!(">{}</text>", path_finder.g_score[end])));
+        assert_eq!(drawn.matches("<circle").count(), 2);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    path_finder_open_set_is_empty, path_finder_open_set_pop, path_finder_open_set_push,
+    CornerCutting, Euclidean, Heuristic, PathFinderBase, PathFinderError, Storage,
+};
+
+impl<S: Storage> PathFinderBase<S> {
+    /// Searches from start to end with Theta*, letting every cell take its grandparent as parent
+    /// when it is in sight. `parents` then only links the waypoints of the path, which
+    /// [`path`](Self::path) lists, and no cell gets the 0x8 path mark. Segments cost their
+    /// Euclidean length times `orthogonal_cost`, so `diagonal_cost`, `cost_func`, `score_func`
+    /// and `heuristic_func` are ignored.
+    pub fn find_any_angle(&mut self) -> Result<bool, PathFinderError> {
+        self.check_endpoints()?;
+        let start = self.start as usize;
+        self.parents[start] = self.start;
+        self.g_score[start] = 0;
+        self.begin();
+
+        while path_finder_open_set_is_empty(self) == 0 {
+            let current = path_finder_open_set_pop(self) as usize;
+            self.state[current] |= 0x4;
+            if current == self.end as usize {
+                self.has_path = 1;
+                self.path_weight = 1.0;
+                return Ok(true);
+            }
+
+            let [col, row] = self.position(current);
+            for &offset in self.neighbor_offsets() {
+                let n = match self.neighbor(col, row, offset) {
+                    Some((n, _)) => n,
+                    None => continue,
+                };
+                if self.state[n] & 0x1 == 0 || self.state[n] & 0x4 != 0 {
+                    continue;
+                }
+
+                let neighbor = self.position(n);
+                let grandparent = self.parents[current] as usize;
+                let parent = if self.line_of_sight(self.position(grandparent), neighbor) {
+                    grandparent
+                } else {
+                    current
+                };
+                let g_score =
+                    self.g_score[parent] + self.segment_cost(self.position(parent), neighbor);
+                if self.state[n] & 0x2 == 0 || g_score < self.g_score[n] {
+                    self.parents[n] = parent as i32;
+                    self.g_score[n] = g_score;
+                    self.f_score[n] = g_score
+                        + Euclidean.estimate(
+                            neighbor,
+                            self.position(self.end as usize),
+                            self.step_costs(),
+                        );
+                    path_finder_open_set_push(self, n as i32);
+                }
+            }
+        }
+
+        self.has_path = 0;
+        Ok(false)
+    }
+
+    pub(crate) fn segment_cost(&self, [col, row]: [i32; 2], [to_col, to_row]: [i32; 2]) -> i32 {
+        let length = f64::from(to_col - col).hypot(f64::from(to_row - row));
+        (f64::from(self.orthogonal_cost) * length).round() as i32
+    }
+
+    /// Whether the straight line between the centres of the two cells only crosses passable
+    /// cells. Where the line goes exactly through a corner, the two cells touching it are checked
+    /// against `corner_cutting`.
+    pub fn line_of_sight(&self, [mut col, mut row]: [i32; 2], [to_col, to_row]: [i32; 2]) -> bool {
+        let passable = |col: i32, row: i32| {
+            col >= 0
+                && col < self.cols
+                && row >= 0
+                && row < self.rows
+                && self.state[self.index(col, row)] & 0x1 != 0
+        };
+        if !passable(col, row) {
+            return false;
+        }
+
+        let (cols, rows) = ((to_col - col).abs(), (to_row - row).abs());
+        let (step_col, step_row) = ((to_col - col).signum(), (to_row - row).signum());
+        /* Positive when the line leaves the current cell through a vertical side, negative through
+         * a horizontal one and zero through a corner */
+        let mut error = cols - rows;
+        while [col, row] != [to_col, to_row] {
+            if error > 0 {
+                col += step_col;
+                error -= 2 * rows;
+            } else if error < 0 {
+                row += step_row;
+                error += 2 * cols;
+            } else {
+                let horizontal = passable(col + step_col, row);
+                let vertical = passable(col, row + step_row);
+                let allowed = match self.corner_cutting {
+                    CornerCutting::Always => true,
+                    CornerCutting::OneSideOpen => horizontal || vertical,
+                    CornerCutting::Never => horizontal && vertical,
+                };
+                if !allowed {
+                    return false;
+                }
+
+                col += step_col;
+                row += step_row;
+                error += 2 * (cols - rows);
+            }
+
+            if !passable(col, row) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{cross_check, random_endpoints, random_grid},
+        *,
+    };
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn find_any_angle() {
+        /*
+         * Representation
+         *
+         * /----------\
+         * |S#        |
+         * |  #       |
+         * |          |
+         * |         E|
+         * \----------/
+         */
+
+        let mut path_finder = HeapPathFinder::new(10, 4).unwrap();
+        path_finder.movement = Movement::EightConnected;
+        path_finder.orthogonal_cost = 100;
+        path_finder.diagonal_cost = 142;
+        path_finder.set_end(9, 3).unwrap();
+
+        let mut open = path_finder.clone();
+        assert_eq!(open.find_any_angle(), Ok(true));
+        let mut small: PathFinder = PathFinder {
+            cols: 10,
+            rows: 4,
+            end: 39,
+            state: [0x1; PATH_FINDER_MAX_CELLS],
+            ..Default::default()
+        };
+        assert_eq!(path_finder_find_any_angle(&mut small), PATH_FINDER_OK);
+        assert_eq!(path_finder_has_line_of_sight(&small, 0, 0, 9, 3), 1);
+        assert_eq!(small.path(), open.path());
+        assert_eq!(open.path(), [[0, 0], [9, 3]]);
+        assert_eq!(open.g_score[39], 949);
+        assert!(open.state.iter().all(|&state| state & 0x8 == 0));
+
+        path_finder
+            .fill_with(|col, row| ![[1, 0], [2, 1]].contains(&[col, row]))
+            .unwrap();
+        assert!(!path_finder.line_of_sight([0, 0], [4, 2]));
+        assert!(path_finder.line_of_sight([0, 0], [3, 3]));
+        assert!(path_finder.line_of_sight([0, 0], [2, 2]));
+        path_finder.corner_cutting = CornerCutting::OneSideOpen;
+        assert!(path_finder.line_of_sight([0, 0], [2, 2]));
+        path_finder.corner_cutting = CornerCutting::Never;
+        assert!(!path_finder.line_of_sight([0, 0], [2, 2]));
+        path_finder.corner_cutting = CornerCutting::Always;
+
+        let mut walled = path_finder.clone();
+        assert_eq!(walled.find_any_angle(), Ok(true));
+        assert_eq!(walled.path(), [[0, 0], [2, 2], [9, 3]]);
+        assert_eq!(walled.g_score[39], 283 + 707);
+
+        /* Random grids: waypoints in sight of each other, far fewer than the cells of the grid
+         * path, and never costlier than it */
+        let mut rng = SmallRng::seed_from_u64(16);
+        let costs = vec![1; 40 * 30];
+        let (mut waypoint_count, mut cell_count) = (0, 0);
+        for _map in 0..40 {
+            let mut any_angle =
+                random_grid(&mut rng, [40, 30], Movement::EightConnected, [1, 4], &[]);
+            any_angle.orthogonal_cost = 100;
+            any_angle.diagonal_cost = 142;
+            any_angle.corner_cutting = CornerCutting::Never;
+            let [_, end] = random_endpoints(&mut rng, &mut any_angle);
+
+            let found = any_angle.find_any_angle().unwrap();
+            let grid = match cross_check(&any_angle, found, &costs) {
+                Some(grid) => grid,
+                None => continue,
+            };
+
+            let waypoints = any_angle.path();
+            let length: i32 = waypoints
+                .windows(2)
+                .map(|segment| {
+                    assert!(any_angle.line_of_sight(segment[0], segment[1]));
+                    any_angle.segment_cost(segment[0], segment[1])
+                })
+                .sum();
+            assert_eq!(length, any_angle.g_score[end]);
+            waypoint_count += waypoints.len();
+            cell_count += grid.path().len();
+            assert!(any_angle.g_score[end] <= grid.g_score[end]);
+            assert!(any_angle.state.iter().all(|&state| state & 0x8 == 0));
+        }
+        assert!(waypoint_count * 2 < cell_count);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    mem::{offset_of, size_of},
+    os::raw::c_void,
+    path::Path,
+    process::Command,
+};
+use uastar::*;
+
+const FIELDS: [&str; 25] = [
+    "cols",
+    "rows",
+    "start",
+    "end",
+    "has_path",
+    "path_weight",
+    "state",
+    "parents",
+    "g_score",
+    "f_score",
+    "open_set",
+    "open_set_index",
+    "open_set_size",
+    "movement",
+    "orthogonal_cost",
+    "diagonal_cost",
+    "corner_cutting",
+    "heuristic",
+    "heuristic_weight",
+    "fill_func",
+    "score_func",
+    "cost_func",
+    "heuristic_func",
+    "data",
+    /* The size of the struct */
+    "",
+];
+
+macro_rules! layout {
+    ($name:literal, $type:ty) => {{
+        let offsets = [
+            offset_of!($type, cols),
+            offset_of!($type, rows),
+            offset_of!($type, start),
+            offset_of!($type, end),
+            offset_of!($type, has_path),
+            offset_of!($type, path_weight),
+            offset_of!($type, state),
+            offset_of!($type, parents),
+            offset_of!($type, g_score),
+            offset_of!($type, f_score),
+            offset_of!($type, open_set),
+            offset_of!($type, open_set_index),
+            offset_of!($type, open_set_size),
+            offset_of!($type, movement),
+            offset_of!($type, orthogonal_cost),
+            offset_of!($type, diagonal_cost),
+            offset_of!($type, corner_cutting),
+            offset_of!($type, heuristic),
+            offset_of!($type, heuristic_weight),
+            offset_of!($type, fill_func),
+            offset_of!($type, score_func),
+            offset_of!($type, cost_func),
+            offset_of!($type, heuristic_func),
+            offset_of!($type, data),
+            size_of::<$type>(),
+        ];
+        FIELDS
+            .iter()
+            .zip(&offsets)
+            .map(|(field, offset)| format!("{} {} {}", $name, field, offset))
+            .collect::<Vec<_>>()
+    }};
+}
+
+/* The C spelling of the Rust types crossing the C ABI */
+trait CType {
+    fn c_type() -> String;
+}
+
+macro_rules! c_type {
+    ($($type:ty => $c_type:literal),* $(,)?) => {
+        $(
+            impl CType for $type {
+                fn c_type() -> String {
+                    $c_type.to_owned()
+                }
+            }
+        )*
+    };
+}
+
+c_type! {
+    () => "void",
+    u8 => "uint8_t",
+    i32 => "int32_t",
+    usize => "size_t",
+    *mut c_void => "void *",
+    *mut i32 => "int32_t *",
+    *mut u8 => "uint8_t *",
+    *const u8 => "const uint8_t *",
+}
+
+macro_rules! c_struct {
+    ($($type:ty => $name:literal),* $(,)?) => {
+        $(
+            impl CType for &mut $type {
+                fn c_type() -> String {
+                    concat!("struct ", $name, " *").to_owned()
+                }
+            }
+
+            impl CType for &$type {
+                fn c_type() -> String {
+                    concat!("const struct ", $name, " *").to_owned()
+                }
+            }
+        )*
+    };
+}
+
+c_struct! {
+    PathFinder => "path_finder",
+    PathFinder64 => "path_finder_64",
+    PathFinder256 => "path_finder_256",
+    PathFinder4096 => "path_finder_4096",
+}
+
+/* The C declaration of a function pointer called `name`, from its Rust type */
+trait Prototype {
+    fn declare(name: &str) -> String;
+}
+
+macro_rules! prototype {
+    ($($arg:ident),*) => {
+        impl<R: CType, $($arg: CType),*> Prototype for extern "C" fn($($arg),*) -> R {
+            fn declare(name: &str) -> String {
+                let args: &[String] = &[$($arg::c_type()),*];
+                format!("{} (*{})({})", R::c_type(), name, args.join(", "))
+            }
+        }
+
+        impl<R: CType, $($arg: CType),*> Prototype for unsafe extern "C" fn($($arg),*) -> R {
+            fn declare(name: &str) -> String {
+                <extern "C" fn($($arg),*) -> R>::declare(name)
+            }
+        }
+    };
+}
+
+prototype!(A);
+prototype!(A, B);
+prototype!(A, B, C);
+prototype!(A, B, C, D);
+prototype!(A, B, C, D, E);
+
+fn declare<F: Prototype>(_function: F, name: &str) -> String {
+    F::declare(name)
+}
+
+fn declare_callback<F: Prototype>(_callback: Option<F>, name: &str) -> String {
+    F::declare(name)
+}
+
+/* The functions exported for the default path finder, named as in the generated modules */
+mod path_finder {
+    pub use uastar::{
+        path_finder_add_source as add_source, path_finder_begin as begin,
+        path_finder_clear_goals as clear_goals, path_finder_clear_path as clear_path,
+        path_finder_distance_map as distance_map,
+        path_finder_distance_map_from_sources as distance_map_from_sources,
+        path_finder_fill as fill, path_finder_find as find,
+        path_finder_find_any_angle as find_any_angle,
+        path_finder_find_bidirectional as find_bidirectional,
+        path_finder_find_from_sources as find_from_sources,
+        path_finder_find_jump_points as find_jump_points, path_finder_find_nearest as find_nearest,
+        path_finder_find_step as find_step, path_finder_get_distance as get_distance,
+        path_finder_get_heuristic_score as get_heuristic_score, path_finder_get_path as get_path,
+        path_finder_get_source as get_source, path_finder_has_line_of_sight as has_line_of_sight,
+        path_finder_initialize as initialize, path_finder_is_closed as is_closed,
+        path_finder_is_end as is_end, path_finder_is_goal as is_goal,
+        path_finder_is_open as is_open, path_finder_is_passable as is_passable,
+        path_finder_is_path as is_path, path_finder_is_source as is_source,
+        path_finder_is_start as is_start, path_finder_load as load, path_finder_save as save,
+        path_finder_set_end as set_end, path_finder_set_goal as set_goal,
+        path_finder_set_start as set_start,
+    };
+}
+
+/* Assigns every exported function of `$module` to a pointer of the type its Rust signature
+ * spells in C, so that the C compiler rejects any declaration of the header that differs */
+macro_rules! exports {
+    (
+        $module:ident;
+        $($name:ident($($arg:tt),*)),*;
+        unsafe $($unsafe_name:ident($($unsafe_arg:tt),*)),*
+    ) => {{
+        let prefix = concat!(stringify!($module), "_");
+        let mut checks = Vec::new();
+        $(
+            let function = $module::$name as extern "C" fn($($arg),*) -> _;
+            checks.push((prefix, stringify!($name), declare(function, "check")));
+        )*
+        $(
+            let function = $module::$unsafe_name as unsafe extern "C" fn($($unsafe_arg),*) -> _;
+            checks.push((prefix, stringify!($unsafe_name), declare(function, "check")));
+        )*
+        checks
+    }};
+}
+
+macro_rules! all_exports {
+    ($($module:ident),*) => {{
+        let mut checks = Vec::new();
+        $(
+            checks.extend(exports! {
+                $module;
+                fill(_), begin(_), find_step(_, _), find(_, _), get_heuristic_score(_, _, _),
+                is_passable(_, _, _), is_closed(_, _, _), is_open(_, _, _), is_path(_, _, _),
+                is_start(_, _, _), is_end(_, _, _), set_start(_, _, _), set_end(_, _, _),
+                find_bidirectional(_, _), find_any_angle(_), has_line_of_sight(_, _, _, _, _),
+                find_jump_points(_), find_nearest(_, _), is_goal(_, _, _), set_goal(_, _, _),
+                clear_goals(_), find_from_sources(_, _), is_source(_, _, _),
+                add_source(_, _, _, _), get_source(_, _, _), distance_map_from_sources(_, _),
+                distance_map(_, _), get_distance(_, _, _), clear_path(_), initialize(_);
+                unsafe get_path(_, _, _), save(_, _, _), load(_, _, _)
+            });
+        )*
+        checks
+    }};
+}
+
+/* Reads every callback of a `$type` through a pointer of the type its Rust signature spells in
+ * C, so that the C compiler rejects any field of the header that differs */
+macro_rules! callbacks {
+    ($($type:ty => $name:literal),*) => {{
+        let mut program = String::new();
+        $(
+            let path_finder = <$type>::default();
+            let fill_func: Option<extern "C" fn(_, _, _) -> _> = path_finder.fill_func;
+            let score_func: Option<extern "C" fn(_, _, _, _) -> _> = path_finder.score_func;
+            let cost_func: Option<extern "C" fn(_, _, _, _) -> _> = path_finder.cost_func;
+            let heuristic_func: Option<extern "C" fn(_, _, _) -> _> = path_finder.heuristic_func;
+            program.push_str(&format!(
+                "void check_{}_callbacks(struct {} *path_finder) {{\n",
+                $name, $name
+            ));
+            for (field, declaration) in [
+                ("fill_func", declare_callback(fill_func, "fill_func")),
+                ("score_func", declare_callback(score_func, "score_func")),
+                ("cost_func", declare_callback(cost_func, "cost_func")),
+                ("heuristic_func", declare_callback(heuristic_func, "heuristic_func")),
+            ]
+            .iter()
+            {
+                program.push_str(&format!("    {} = path_finder->{};\n", declaration, field));
+            }
+            program.push_str("}\n");
+        )*
+        program
+    }};
+}
+
+/* Builds `program` against uastar.h and runs it, unless no C compiler is available */
+fn probe(name: &str, program: &str, run: bool) -> Option<String> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let source = directory.join(format!("{}.c", name));
+    let binary = directory.join(name);
+    fs::write(&source, program).unwrap();
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let mut command = Command::new(&compiler);
+    command.arg("-Werror").arg("-I").arg(root).arg(&source);
+    if run {
+        command.arg("-o").arg(&binary);
+    } else {
+        command.arg("-fsyntax-only");
+    }
+    let status = match command.status() {
+        Ok(status) => status,
+        Err(error) => {
+            eprintln!(
+                "skipping the uastar.h check, {} is unavailable: {}",
+                compiler, error
+            );
+            return None;
+        }
+    };
+    assert!(status.success(), "uastar.h does not match the crate");
+    if !run {
+        return Some(String::new());
+    }
+
+    let output = Command::new(&binary).output().unwrap();
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn header_matches_exports() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let header = fs::read_to_string(root.join("uastar.h")).unwrap();
+    let checks = all_exports!(
+        path_finder,
+        path_finder_64,
+        path_finder_256,
+        path_finder_4096
+    );
+
+    /* Every function declared for a path finder is checked */
+    let declared: BTreeSet<_> = header
+        .split("name##_")
+        .skip(1)
+        .map(|rest| rest.split('(').next().unwrap())
+        .collect();
+    let checked: BTreeSet<_> = checks.iter().map(|&(_, name, _)| name).collect();
+    assert_eq!(declared, checked);
+
+    let mut program = String::from("#include \"uastar.h\"\n");
+    for (prefix, name, declaration) in &checks {
+        let pointer = format!("check_{}{}", prefix, name);
+        program.push_str(&format!(
+            "{} = {}{};\n",
+            declaration.replacen("check", &pointer, 1),
+            prefix,
+            name
+        ));
+    }
+    probe("uastar_exports", &program, false);
+}
+
+#[test]
+fn header_matches_callbacks() {
+    let program = callbacks!(
+        PathFinder => "path_finder",
+        PathFinder64 => "path_finder_64",
+        PathFinder256 => "path_finder_256",
+        PathFinder4096 => "path_finder_4096"
+    );
+    probe(
+        "uastar_callbacks",
+        &format!("#include \"uastar.h\"\n{}", program),
+        false,
+    );
+}
+
+#[test]
+fn header_matches_layout() {
+    let mut program = String::from("#include <stdio.h>\n#include \"uastar.h\"\nint main(void) {\n");
+    for name in [
+        "path_finder",
+        "path_finder_64",
+        "path_finder_256",
+        "path_finder_4096",
+    ]
+    .iter()
+    {
+        for field in FIELDS.iter() {
+            let offset = if field.is_empty() {
+                format!("sizeof(struct {})", name)
+            } else {
+                format!("offsetof(struct {}, {})", name, field)
+            };
+            program.push_str(&format!(
+                "    printf(\"{} {} %zu\\n\", {});\n",
+                name, field, offset
+            ));
+        }
+    }
+    program.push_str(
+        "    printf(\"enums %zu %zu %zu\\n\", sizeof(enum movement), \
+         sizeof(enum corner_cutting), sizeof(enum heuristic_kind));\n\
+         printf(\"errors %d %d\\n\", PATH_FINDER_ERROR_OUT_OF_BOUNDS, \
+         PATH_FINDER_ERROR_INVALID_FORMAT);\n\
+         return 0;\n}\n",
+    );
+    let output = match probe("uastar_layout", &program, true) {
+        Some(output) => output,
+        None => return,
+    };
+
+    let mut expected = Vec::new();
+    expected.extend(layout!("path_finder", PathFinder));
+    expected.extend(layout!("path_finder_64", PathFinder64));
+    expected.extend(layout!("path_finder_256", PathFinder256));
+    expected.extend(layout!("path_finder_4096", PathFinder4096));
+    expected.push(format!(
+        "enums {} {} {}",
+        size_of::<Movement>(),
+        size_of::<CornerCutting>(),
+        size_of::<HeuristicKind>()
+    ));
+    expected.push(format!(
+        "errors {} {}",
+        PathFinderError::OutOfBounds.code(),
+        PathFinderError::InvalidFormat.code()
+    ));
+    assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+}
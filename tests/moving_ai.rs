@@ -0,0 +1,105 @@
+use std::fs;
+use uastar::*;
+
+#[test]
+fn sample_scenarios() {
+    let map: MovingAiMap = fs::read_to_string("tests/sample.map")
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!([map.width, map.height], [48, 32]);
+    let path_finder = map.path_finder().unwrap();
+    assert_eq!(path_finder.is_passable(4, 4), 1);
+    assert_eq!(path_finder.is_passable(22, 3), 0);
+    assert_eq!(path_finder.is_passable(10, 7), 0);
+    assert_eq!(path_finder.is_passable(26, 18), 0);
+    assert_eq!(path_finder.is_passable(4, 20), 1);
+
+    let scenarios = Scenario::parse_all(&fs::read_to_string("tests/sample.scen").unwrap()).unwrap();
+    assert_eq!(scenarios.len(), 16);
+    assert_eq!(
+        scenarios[0],
+        Scenario {
+            bucket: 3,
+            map: "sample.map".to_owned(),
+            width: 48,
+            height: 32,
+            start: [26, 24],
+            goal: [32, 13],
+            optimal_length: 15.24264069,
+        }
+    );
+
+    let results = map.run(&scenarios).unwrap();
+    assert_eq!(results.len(), scenarios.len());
+    for result in &results {
+        assert!(result.is_optimal(), "{:?}", result);
+        assert!(result.expanded > 0);
+    }
+}
+
+#[test]
+fn invalid_files() {
+    let parse = |text: &str| text.parse::<MovingAiMap>();
+    assert_eq!(
+        parse("type octile\nheight 2\nwidth 3\nmap\n.@.\nT.W\n").map(|map| map.terrain),
+        Ok(b".@.T.W".to_vec())
+    );
+    assert_eq!(
+        parse("type octile\nheight 2\nwidth 3\nmap\n.@.\n"),
+        Err(PathFinderError::InvalidFormat)
+    );
+    assert_eq!(
+        parse("type octile\nheight 1\nwidth 3\nmap\n.x.\n"),
+        Err(PathFinderError::InvalidFormat)
+    );
+    assert_eq!(
+        parse("type octile\nheight 0\nwidth 3\nmap\n"),
+        Err(PathFinderError::InvalidSize)
+    );
+    assert_eq!(
+        parse("height 1\nwidth 1\n"),
+        Err(PathFinderError::InvalidFormat)
+    );
+
+    assert_eq!(
+        Scenario::parse_all("version 2\n"),
+        Err(PathFinderError::InvalidFormat)
+    );
+    assert_eq!(
+        Scenario::parse_all("version 1\n0\ta.map\t4\t4\t0\t0\t3\n"),
+        Err(PathFinderError::InvalidFormat)
+    );
+
+    let map = parse("type octile\nheight 1\nwidth 3\nmap\n.@.\n").unwrap();
+    let scenarios =
+        Scenario::parse_all("version 1\n0\ta.map\t3\t1\t0\t0\t2\t0\t2.00000000\n").unwrap();
+    let results = map.run(&scenarios).unwrap();
+    assert_eq!(results[0].length, None);
+    assert_eq!(results[0].error, None);
+    assert!(!results[0].is_optimal());
+
+    /* Queries from a wall or off the map fail without stopping the others */
+    let scenarios = Scenario::parse_all(
+        "version 1\n\
+         0\ta.map\t3\t1\t1\t0\t2\t0\t1.00000000\n\
+         0\ta.map\t3\t1\t0\t0\t3\t0\t3.00000000\n\
+         0\ta.map\t3\t1\t2\t0\t2\t0\t0.00000000\n",
+    )
+    .unwrap();
+    let results = map.run(&scenarios).unwrap();
+    let errors: Vec<_> = results.iter().map(|result| result.error).collect();
+    assert_eq!(
+        errors,
+        [
+            Some(PathFinderError::StartImpassable),
+            Some(PathFinderError::OutOfBounds),
+            None
+        ]
+    );
+    assert!(results[2].is_optimal());
+
+    let scenarios =
+        Scenario::parse_all("version 1\n0\ta.map\t4\t4\t0\t0\t2\t0\t2.00000000\n").unwrap();
+    assert_eq!(map.run(&scenarios), Err(PathFinderError::InvalidSize));
+}
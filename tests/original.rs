@@ -1,36 +1,15 @@
-use once_cell::sync::{Lazy, OnceCell};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use std::{
-    cell::{Cell, RefCell},
     error,
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
     ptr::null_mut,
-    sync::Mutex,
     thread::sleep,
     time::Duration,
 };
 use uastar::*;
 
-static PASSABLE_CHANCE: Lazy<Mutex<Cell<u32>>> = Lazy::new(|| Mutex::new(Cell::new(0)));
-
-static RNG: OnceCell<Mutex<RefCell<SmallRng>>> = OnceCell::new();
-
-fn fill_cb(_path_finder: &mut PathFinder, _col: i32, _row: i32) -> u8 {
-    let mut is_passable = 0u8;
-    /* Fill the map randomly with passable cells */
-    let rand_value = RNG.get().unwrap().lock().unwrap().borrow_mut().gen::<i32>();
-
-    if rand_value as f64 / 2_147_483_647_f64
-        <= PASSABLE_CHANCE.lock().unwrap().get() as f64 / 100.0f64
-    {
-        is_passable = 1u8
-    }
-
-    is_passable
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Pos {
     col: i32,
@@ -65,13 +44,10 @@ fn find_path(
         fill_func: None,
         score_func: None,
         data: null_mut(),
+        ..Default::default()
     };
 
-    PASSABLE_CHANCE.lock().unwrap().set(chance);
-    RNG.set(Mutex::new(RefCell::new(SmallRng::seed_from_u64(
-        seed as u64,
-    ))))
-    .unwrap();
+    let mut rng = SmallRng::seed_from_u64(seed as u64);
     if width < 1 || height < 1 || height as usize * width as usize > PATH_FINDER_MAX_CELLS {
         println!("Failed due width or height smaller than 1 or the number of cells (width * height) is larger than {}.", PATH_FINDER_MAX_CELLS);
     } else if start.col < 0
@@ -88,9 +64,13 @@ fn find_path(
         path_finder_initialize(&mut path_finder);
         path_finder.cols = width;
         path_finder.rows = height;
-        path_finder.fill_func = Some(fill_cb);
         path_finder.score_func = None;
-        path_finder_fill(&mut path_finder);
+        /* Fill the map randomly with passable cells */
+        path_finder
+            .fill_with(|_col, _row| {
+                rng.gen::<i32>() as f64 / 2_147_483_647_f64 <= chance as f64 / 100.0f64
+            })
+            .unwrap();
         path_finder_set_start(&mut path_finder, start.col, start.row);
         path_finder_set_end(&mut path_finder, end.col, end.row);
         if show_progress == 0 {
@@ -150,9 +130,7 @@ fn from_file(path: &Path) -> Result<PathFinder, Box<dyn error::Error>> {
         parents,
         g_score,
         f_score,
-        fill_func: None,
-        score_func: None,
-        data: null_mut(),
+        ..Default::default()
     })
 }
 
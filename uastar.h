@@ -0,0 +1,121 @@
+/*
+ * C declarations of the uastar entry points and of the layout of the path finders they take.
+ *
+ * Every fixed-capacity path finder exported by the crate is declared by UASTAR_PATH_FINDER,
+ * along with its functions, and tests/c_header.rs checks this file against the Rust layout and
+ * signatures.
+ * Keep both in sync when changing a #[repr(C)] type or an exported function.
+ */
+
+#ifndef UASTAR_H
+#define UASTAR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define PATH_FINDER_MAX_CELLS 1024
+#define PATH_FINDER_UNREACHABLE -1
+#define PATH_FINDER_MAX_GOAL_ESTIMATES 16
+#define PATH_FINDER_SAVE_VERSION 1
+
+#define PATH_FINDER_OK 0
+
+enum path_finder_error {
+    PATH_FINDER_ERROR_OUT_OF_BOUNDS = 1,
+    PATH_FINDER_ERROR_INVALID_SIZE,
+    PATH_FINDER_ERROR_GRID_TOO_LARGE,
+    PATH_FINDER_ERROR_MISSING_FILL_FUNC,
+    PATH_FINDER_ERROR_START_IMPASSABLE,
+    PATH_FINDER_ERROR_END_IMPASSABLE,
+    PATH_FINDER_ERROR_INVALID_FORMAT,
+};
+
+enum movement {
+    MOVEMENT_FOUR_CONNECTED,
+    MOVEMENT_EIGHT_CONNECTED,
+};
+
+enum corner_cutting {
+    CORNER_CUTTING_ALWAYS,
+    CORNER_CUTTING_ONE_SIDE_OPEN,
+    CORNER_CUTTING_NEVER,
+};
+
+enum heuristic_kind {
+    HEURISTIC_KIND_AUTO,
+    HEURISTIC_KIND_MANHATTAN,
+    HEURISTIC_KIND_CHEBYSHEV,
+    HEURISTIC_KIND_OCTILE,
+    HEURISTIC_KIND_EUCLIDEAN,
+    HEURISTIC_KIND_ZERO,
+};
+
+#define UASTAR_PATH_FINDER(name, cells)                                                          \
+    struct name {                                                                                \
+        int32_t cols;                                                                            \
+        int32_t rows;                                                                            \
+        int32_t start;                                                                           \
+        int32_t end;                                                                             \
+        uint8_t has_path;                                                                        \
+        float path_weight;                                                                       \
+        uint8_t state[cells];                                                                    \
+        int32_t parents[cells];                                                                  \
+        int32_t g_score[cells];                                                                  \
+        int32_t f_score[cells];                                                                  \
+        int32_t open_set[cells];                                                                 \
+        int32_t open_set_index[cells];                                                           \
+        int32_t open_set_size;                                                                   \
+        enum movement movement;                                                                  \
+        int32_t orthogonal_cost;                                                                 \
+        int32_t diagonal_cost;                                                                   \
+        enum corner_cutting corner_cutting;                                                      \
+        enum heuristic_kind heuristic;                                                           \
+        float heuristic_weight;                                                                  \
+        uint8_t (*fill_func)(struct name *path_finder, int32_t col, int32_t row);                \
+        int32_t (*score_func)(struct name *path_finder, int32_t col, int32_t row, void *data);   \
+        int32_t (*cost_func)(struct name *path_finder, int32_t col, int32_t row, void *data);    \
+        int32_t (*heuristic_func)(const struct name *path_finder, int32_t col, int32_t row);     \
+        void *data;                                                                              \
+    };                                                                                           \
+                                                                                                 \
+    int name##_fill(struct name *path_finder);                                                   \
+    void name##_begin(struct name *path_finder);                                                 \
+    uint8_t name##_find_step(struct name *path_finder, void *data);                              \
+    int name##_find(struct name *path_finder, void *data);                                       \
+    int32_t name##_get_heuristic_score(struct name *path_finder, int32_t col, int32_t row);      \
+    uint8_t name##_is_passable(struct name *path_finder, int32_t col, int32_t row);              \
+    uint8_t name##_is_closed(struct name *path_finder, int32_t col, int32_t row);                \
+    uint8_t name##_is_open(struct name *path_finder, int32_t col, int32_t row);                  \
+    uint8_t name##_is_path(struct name *path_finder, int32_t col, int32_t row);                  \
+    uint8_t name##_is_start(struct name *path_finder, int32_t col, int32_t row);                 \
+    uint8_t name##_is_end(struct name *path_finder, int32_t col, int32_t row);                   \
+    int name##_set_start(struct name *path_finder, int32_t col, int32_t row);                    \
+    int name##_set_end(struct name *path_finder, int32_t col, int32_t row);                      \
+    int name##_find_bidirectional(struct name *path_finder, void *data);                         \
+    int name##_find_any_angle(struct name *path_finder);                                         \
+    uint8_t name##_has_line_of_sight(const struct name *path_finder, int32_t col, int32_t row,   \
+                                     int32_t to_col, int32_t to_row);                            \
+    int name##_find_jump_points(struct name *path_finder);                                       \
+    int name##_find_nearest(struct name *path_finder, void *data);                               \
+    uint8_t name##_is_goal(const struct name *path_finder, int32_t col, int32_t row);            \
+    int name##_set_goal(struct name *path_finder, int32_t col, int32_t row);                     \
+    void name##_clear_goals(struct name *path_finder);                                           \
+    int name##_find_from_sources(struct name *path_finder, void *data);                          \
+    uint8_t name##_is_source(const struct name *path_finder, int32_t col, int32_t row);          \
+    int name##_add_source(struct name *path_finder, int32_t col, int32_t row, int32_t g_score);  \
+    int32_t name##_get_source(const struct name *path_finder, int32_t col, int32_t row);         \
+    int name##_distance_map_from_sources(struct name *path_finder, void *data);                  \
+    int name##_distance_map(struct name *path_finder, void *data);                               \
+    int32_t name##_get_distance(const struct name *path_finder, int32_t col, int32_t row);       \
+    int32_t name##_get_path(const struct name *path_finder, int32_t *buffer, size_t capacity);   \
+    size_t name##_save(const struct name *path_finder, uint8_t *buffer, size_t capacity);        \
+    int name##_load(struct name *path_finder, const uint8_t *bytes, size_t len);                 \
+    void name##_clear_path(struct name *path_finder);                                            \
+    void name##_initialize(struct name *path_finder);
+
+UASTAR_PATH_FINDER(path_finder, PATH_FINDER_MAX_CELLS)
+UASTAR_PATH_FINDER(path_finder_64, 64)
+UASTAR_PATH_FINDER(path_finder_256, 256)
+UASTAR_PATH_FINDER(path_finder_4096, 4096)
+
+#endif /* UASTAR_H */